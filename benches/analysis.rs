@@ -0,0 +1,141 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use wasm_encoder::{CodeSection, ConstExpr, ElementSection, Elements, ExportKind, ExportSection,
+                   Function, FunctionSection, Instruction, RefType, TableSection, TableType,
+                   TypeSection, ValType};
+use wasmparser::Payload::{ElementSection as ElementPayload, ExportSection as ExportPayload};
+use wazm::Module;
+
+// Number of functions in the generated module, each with a body of about 1KB
+const FUNCTION_COUNT: u32 = 4_000;
+
+// Generate a module of a few megabytes, with every function in a table and each one calling
+// the next, so that all of the analysis has work to do
+fn large_module() -> Vec<u8> {
+    let mut types = TypeSection::new();
+    types.function([ValType::I32], [ValType::I32]);
+
+    let mut functions = FunctionSection::new();
+    let mut code = CodeSection::new();
+    for index in 0..FUNCTION_COUNT {
+        functions.function(0);
+        let mut body = Function::new([(1, ValType::I32)]);
+        body.instruction(&Instruction::LocalGet(0));
+        for constant in 0..200 {
+            body.instruction(&Instruction::I32Const(constant));
+            body.instruction(&Instruction::I32Add);
+        }
+        body.instruction(&Instruction::LocalTee(1));
+        body.instruction(&Instruction::If(wasm_encoder::BlockType::Result(ValType::I32)));
+        body.instruction(&Instruction::LocalGet(1));
+        body.instruction(&Instruction::Call((index + 1) % FUNCTION_COUNT));
+        body.instruction(&Instruction::Else);
+        body.instruction(&Instruction::LocalGet(0));
+        body.instruction(&Instruction::End);
+        body.instruction(&Instruction::End);
+        code.function(&body);
+    }
+
+    let mut tables = TableSection::new();
+    tables.table(TableType {
+        element_type: RefType::FUNCREF,
+        minimum: FUNCTION_COUNT,
+        maximum: None,
+    });
+    let mut exports = ExportSection::new();
+    exports.export("run", ExportKind::Func, 0);
+    let mut elements = ElementSection::new();
+    let indexes: Vec<u32> = (0..FUNCTION_COUNT).collect();
+    elements.active(None, &ConstExpr::i32_const(0), RefType::FUNCREF,
+                    Elements::Functions(&indexes));
+
+    let mut module = wasm_encoder::Module::new();
+    module.section(&types)
+        .section(&functions)
+        .section(&tables)
+        .section(&exports)
+        .section(&elements)
+        .section(&code);
+    module.finish()
+}
+
+// Generate a module with a single function exported under many names
+fn many_exports_module() -> Vec<u8> {
+    let mut types = TypeSection::new();
+    types.function([], []);
+    let mut functions = FunctionSection::new();
+    functions.function(0);
+    let mut exports = ExportSection::new();
+    for index in 0..20_000 {
+        exports.export(&format!("export_{}", index), ExportKind::Func, 0);
+    }
+    let mut code = CodeSection::new();
+    let mut body = Function::new([]);
+    body.instruction(&Instruction::End);
+    code.function(&body);
+
+    let mut module = wasm_encoder::Module::new();
+    module.section(&types).section(&functions).section(&exports).section(&code);
+    module.finish()
+}
+
+// Compare iterating the items of a section with Module::section_items, as the analysis does,
+// with iterating a clone of the section reader borrowed from the module's payloads, as it did
+fn iterate_section_readers(c: &mut Criterion) {
+    let buf = large_module();
+    let module = Module::from_bytes(&buf).expect("Could not parse generated module");
+    let elements = module.payloads.iter()
+        .find_map(|payload| match payload {
+            ElementPayload(reader) => Some(reader),
+            _ => None,
+        })
+        .expect("No element section");
+    let mut group = c.benchmark_group("element section reader");
+    group.bench_function("clone and iterate", |b| b.iter(|| {
+        elements.clone().into_iter().flatten().count()
+    }));
+    group.bench_function("section items", |b| b.iter(|| {
+        module.section_items(elements).expect("Could not read element section").flatten().count()
+    }));
+    group.finish();
+
+    let buf = many_exports_module();
+    let module = Module::from_bytes(&buf).expect("Could not parse generated module");
+    let exports = module.payloads.iter()
+        .find_map(|payload| match payload {
+            ExportPayload(reader) => Some(reader),
+            _ => None,
+        })
+        .expect("No export section");
+    let mut group = c.benchmark_group("export section reader");
+    group.bench_function("clone and iterate", |b| b.iter(|| {
+        exports.clone().into_iter().flatten().count()
+    }));
+    group.bench_function("section items", |b| b.iter(|| {
+        module.section_items(exports).expect("Could not read export section").flatten().count()
+    }));
+    group.finish();
+}
+
+// Analyze everything in the module serially, and in parallel on four threads and on all
+// available cores
+fn analyze_with_jobs(c: &mut Criterion) {
+    let buf = large_module();
+    let module = Module::from_bytes(&buf).expect("Could not parse generated module");
+    let cores = std::thread::available_parallelism().map(|cores| cores.get()).unwrap_or(1);
+
+    let mut group = c.benchmark_group(format!("analyze {} bytes", buf.len()));
+    group.sample_size(20);
+    let mut job_counts = vec![1, 4, cores];
+    job_counts.sort();
+    job_counts.dedup();
+    for jobs in job_counts {
+        group.bench_with_input(BenchmarkId::new("jobs", jobs), &jobs, |b, &jobs| {
+            b.iter(|| wazm::analyze_with_jobs(&module, true, true, true, true, jobs)
+                .expect("Could not analyze"))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, analyze_with_jobs, iterate_section_readers);
+criterion_main!(benches);
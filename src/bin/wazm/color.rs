@@ -0,0 +1,71 @@
+use std::io::IsTerminal;
+use anstyle::{AnsiColor, Style};
+
+const HEADING: Style = Style::new().bold();
+const WARNING: Style = Style::new().fg_color(Some(anstyle::Color::Ansi(AnsiColor::Red))).bold();
+const SIZES: Style = Style::new().fg_color(Some(anstyle::Color::Ansi(AnsiColor::Cyan)));
+const SECTION_TYPE: Style = Style::new().fg_color(Some(anstyle::Color::Ansi(AnsiColor::Green)));
+
+/// Decide if output should be colored from the value of the "--color" option: "always",
+/// "never" or "auto", when it is colored if stdout is a terminal and `NO_COLOR` is not set
+pub fn use_color(choice: &str) -> bool {
+    match choice {
+        "always" => true,
+        "never" => false,
+        _ => std::env::var_os("NO_COLOR").is_none_or(|value| value.is_empty())
+            && std::io::stdout().is_terminal(),
+    }
+}
+
+/// Color the lines of the plain text output of an analysis. Warnings are highlighted, and
+/// the type of each section in the section table is colored differently from its sizes
+pub fn colorize(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    for line in text.split_inclusive('\n') {
+        let (line, newline) = match line.strip_suffix('\n') {
+            Some(line) => (line, "\n"),
+            None => (line, ""),
+        };
+        if line.starts_with("WARNING") {
+            output.push_str(&paint(WARNING, line));
+        } else if let (true, Some(type_start)) = (line.starts_with("0x"), line.find("%  ")) {
+            let (sizes, section_type) = line.split_at(type_start + 3);
+            output.push_str(&paint(SIZES, sizes));
+            output.push_str(&paint(SECTION_TYPE, section_type));
+        } else if line.ends_with(':') && !line.starts_with(char::is_whitespace) {
+            output.push_str(&paint(HEADING, line));
+        } else {
+            output.push_str(line);
+        }
+        output.push_str(newline);
+    }
+    output
+}
+
+/// Color a whole line of text as a warning
+pub fn warning(line: &str) -> String {
+    paint(WARNING, line)
+}
+
+// Wrap `text` in the escape codes to render it in `style`
+fn paint(style: Style, text: &str) -> String {
+    format!("{}{}{}", style.render(), text, style.render_reset())
+}
+
+#[cfg(test)]
+mod test {
+    use super::colorize;
+
+    #[test]
+    fn test_colorize() {
+        let text = "Sections:\n\
+            0x000000000008 : 0x00000000000b - 0x0000000000ae      0xa4       164    0.1%  TypeSection\n\
+            WARNING Memory64: used\n plain\n";
+        let colored = colorize(text);
+        assert_ne!(colored, text);
+        assert!(colored.contains("\u{1b}[1mSections:\u{1b}[0m\n"));
+        assert!(colored.contains("\u{1b}[32mTypeSection\u{1b}[0m\n"));
+        assert!(colored.contains("WARNING Memory64: used\u{1b}[0m\n"));
+        assert!(colored.ends_with("\n plain\n"));
+    }
+}
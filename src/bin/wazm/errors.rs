@@ -1,4 +1,5 @@
 #![allow(missing_docs)]
+#![allow(unexpected_cfgs)]
 
 pub use error_chain::bail;
 use error_chain::error_chain;
@@ -11,5 +12,6 @@ error_chain! {
     foreign_links {
         Io(std::io::Error);
         Wazm(wazm::errors::Error);
+        Json(serde_json::Error);
     }
 }
@@ -0,0 +1,177 @@
+use std::fs::File;
+use std::io::Read;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use memmap2::Mmap;
+use wax::Glob;
+use wazm::header::MAGIC;
+use crate::errors::{bail, Result};
+
+/// Files at least this large are memory-mapped rather than read when they are analyzed
+pub const MMAP_THRESHOLD: u64 = 64 * 1024 * 1024;
+
+/// The bytes of an input file, either read into memory or memory-mapped
+pub enum Input {
+    Read(Vec<u8>),
+    Mapped(Mmap),
+}
+
+impl Deref for Input {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            Input::Read(buf) => buf,
+            Input::Mapped(map) => map,
+        }
+    }
+}
+
+/// Get the bytes of the file at `path` for reading only, memory-mapping it if `mmap` is set or
+/// it is at least [MMAP_THRESHOLD] bytes long. A ".wat" file is compiled to WASM bytes first
+pub fn read_input(path: &Path, mmap: bool) -> Result<Input> {
+    if path.extension() == Some("wat".as_ref()) {
+        let text = std::fs::read_to_string(path)?;
+        return Ok(Input::Read(wazm::parse::wat_to_wasm(&text)?));
+    }
+
+    let file = File::open(path)?;
+    if mmap || file.metadata()?.len() >= MMAP_THRESHOLD {
+        // Safety: the mapping is only read, and is only valid as long as the file is not
+        // modified by another process while it is being analyzed
+        let map = unsafe { Mmap::map(&file)? };
+        Ok(Input::Mapped(map))
+    } else {
+        Ok(Input::Read(std::fs::read(path)?))
+    }
+}
+
+/// The format of an input file
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    /// A WASM module, that is compressed
+    Wasm,
+    /// A ".wz" file, that is decompressed
+    Wazm,
+    /// A ".wat" file of WebAssembly text, that can only be analyzed
+    Wat,
+}
+
+/// Determine the format of the file at `path` from the magic bytes it starts with, falling
+/// back to its ".wasm", ".wz" or ".wat" extension only when it starts with neither
+pub fn detect_format(path: &Path) -> Result<Format> {
+    let mut start = vec![];
+    File::open(path)?.take(MAGIC.len() as u64).read_to_end(&mut start)?;
+    if start == b"\0asm" {
+        return Ok(Format::Wasm);
+    }
+    if start == MAGIC {
+        return Ok(Format::Wazm);
+    }
+
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some("wasm") => Ok(Format::Wasm),
+        Some("wz") => Ok(Format::Wazm),
+        Some("wat") => Ok(Format::Wat),
+        _ => bail!("File '{}' is neither a WASM file nor a wazm file", path.display()),
+    }
+}
+
+/// Expand the file arguments into the list of files to process. A directory is searched
+/// recursively for ".wasm" and ".wz" files, and an argument that is not an existing path but
+/// is a glob pattern is replaced by the files that match it. Any other argument is kept as is
+pub fn input_files<S: AsRef<str>>(args: &[S]) -> Result<Vec<PathBuf>> {
+    let mut files = vec![];
+    for arg in args {
+        let arg = arg.as_ref();
+        let path = Path::new(arg);
+        if path.is_dir() {
+            files.extend(walk(Glob::new("**/*.{wasm,wz}").map_err(|e| e.to_string())?, path)?);
+        } else if !path.exists() && arg.contains(['*', '?', '[', '{']) {
+            let (prefix, glob) = Glob::new(arg).map_err(|e| e.to_string())?.partition();
+            let prefix = if prefix.as_os_str().is_empty() { PathBuf::from(".") } else { prefix };
+            files.extend(walk(glob, &prefix)?);
+        } else {
+            files.push(path.to_path_buf());
+        }
+    }
+    Ok(files)
+}
+
+// Find the files under `directory` that match `glob`, sorted by path
+fn walk(glob: Glob, directory: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = vec![];
+    for entry in glob.walk(directory) {
+        let entry = entry.map_err(std::io::Error::from)?;
+        if entry.path().is_file() {
+            files.push(entry.into_path());
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+    use std::path::PathBuf;
+    use super::{detect_format, input_files, read_input, Format, Input};
+
+    #[test]
+    fn test_detect_format() {
+        let dir = std::env::temp_dir();
+        let wasm = dir.join(format!("detect_wasm_{}", std::process::id()));
+        fs::write(&wasm, b"\0asm\x01\0\0\0").expect("Could not write wasm file");
+        assert_eq!(detect_format(&wasm).expect("Could not detect format"), Format::Wasm);
+
+        // the magic bytes are used even when the extension says otherwise
+        let wazm = dir.join(format!("detect_wazm_{}.wasm", std::process::id()));
+        fs::write(&wazm, b"WZ01\x03").expect("Could not write wazm file");
+        assert_eq!(detect_format(&wazm).expect("Could not detect format"), Format::Wazm);
+
+        let truncated = dir.join(format!("detect_truncated_{}.wz", std::process::id()));
+        fs::write(&truncated, b"W").expect("Could not write truncated file");
+        assert_eq!(detect_format(&truncated).expect("Could not detect format"), Format::Wazm);
+
+        let wat = dir.join(format!("detect_wat_{}.wat", std::process::id()));
+        fs::write(&wat, b"(module)").expect("Could not write wat file");
+        assert_eq!(detect_format(&wat).expect("Could not detect format"), Format::Wat);
+
+        let unknown = dir.join(format!("detect_unknown_{}", std::process::id()));
+        fs::write(&unknown, b"text").expect("Could not write unknown file");
+        assert!(detect_format(&unknown).is_err());
+
+        for file in [wasm, wazm, truncated, wat, unknown] {
+            let _ = fs::remove_file(file);
+        }
+    }
+
+    #[test]
+    fn test_input_files() {
+        let test_files = format!("{}/tests/test_files", env!("CARGO_MANIFEST_DIR"));
+        let wasm = PathBuf::from(&test_files).join("compare_switch_stripped.wasm");
+
+        let files = input_files(&[&test_files]).expect("Could not expand directory");
+        assert!(files.contains(&wasm));
+        assert!(files.iter().all(|file| file.extension() != Some("wat".as_ref())));
+
+        let pattern = format!("{}/*_stripped.wasm", test_files);
+        assert_eq!(input_files(&[&pattern]).expect("Could not expand glob"), vec!(wasm));
+
+        assert_eq!(input_files(&["missing.wasm"]).expect("Could not expand missing file"),
+                   vec!(PathBuf::from("missing.wasm")));
+    }
+
+    #[test]
+    fn test_read_input() {
+        let wasm = PathBuf::from(&format!("{}/tests/test_files/compare_switch_stripped.wasm",
+                                          env!("CARGO_MANIFEST_DIR")));
+        let expected = fs::read(&wasm).expect("Could not read test file");
+        let read = read_input(&wasm, false).expect("Could not read input");
+        let mapped = read_input(&wasm, true).expect("Could not map input");
+        assert!(matches!(read, Input::Read(_)));
+        assert!(matches!(mapped, Input::Mapped(_)));
+        assert_eq!(&*read, expected.as_slice());
+        assert_eq!(&*mapped, expected.as_slice());
+    }
+}
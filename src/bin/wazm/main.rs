@@ -1,14 +1,21 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use clap::{Arg, ArgMatches, Command};
 use std::process::exit;
+use std::io::Write;
 use log::LevelFilter;
 use env_logger::Builder;
 use core::str::FromStr;
 
 mod errors;
+mod color;
+mod input;
 
 use wazm::Module;
-use crate::errors::Result;
+use wazm::analysis::{Callers, SectionSizeDiff, SectionSort};
+use wazm::compress::Strip;
+use wazm::optimize::Optimize;
+use crate::errors::{Result, ResultExt};
+use crate::input::Format;
 use crate::errors::bail;
 
 /// Main for flowr binary - call `run()` and print any error that results or exit silently if OK
@@ -22,63 +29,285 @@ pub fn main() {
 
     match run(matches) {
         Err(ref e) => {
-            eprintln!("{e}");
-            for e in e.iter().skip(1) {
-                eprintln!("caused by: {e}");
-            }
-
-            // The backtrace is generated if env var `RUST_BACKTRACE` is set to `1` or `full`
-            if let Some(backtrace) = e.backtrace() {
-                eprintln!("backtrace: {backtrace:?}");
-            }
-
+            print_error(e);
             exit(1);
         }
         Ok(_) => exit(0),
     }
 }
 
+// Print an error and the chain of errors that caused it
+fn print_error(e: &errors::Error) {
+    eprintln!("{e}");
+    for e in e.iter().skip(1) {
+        eprintln!("caused by: {e}");
+    }
+
+    // The backtrace is generated if env var `RUST_BACKTRACE` is set to `1` or `full`
+    if let Some(backtrace) = e.backtrace() {
+        eprintln!("backtrace: {backtrace:?}");
+    }
+}
+
+// Print text output, colored if requested
+fn print_output(text: &str, color: bool) {
+    if color {
+        print!("{}", color::colorize(text));
+    } else {
+        print!("{}", text);
+    }
+}
+
 fn run(matches: ArgMatches) -> Result<()> {
-    let source_filename = matches.get_one::<String>("wasm-file")
-        .expect("WASM file name must be specified"); // TODO clap to do this
-    let source = Path::new(&source_filename);
+    if let Some(join) = matches.get_many::<String>("join") {
+        let join: Vec<&String> = join.collect();
+        wazm::split::join(Path::new(join[0]), Path::new(join[1]))?;
+        return Ok(());
+    }
+
+    let args: Vec<&String> = matches.get_many::<String>("wasm-file")
+        .expect("WASM file name is required")
+        .collect();
+    let files: Vec<PathBuf> = input::input_files(&args)?;
+    if files.is_empty() {
+        bail!("No .wasm or .wz files found in {}", args.iter()
+            .map(|arg| format!("'{}'", arg)).collect::<Vec<String>>().join(", "));
+    }
+    if files.len() > 1 && matches.contains_id("output") {
+        bail!("--output can only be used with a single file, not {} files", files.len());
+    }
+    if files.len() > 1 && matches.contains_id("split") {
+        bail!("--split can only be used with a single file, not {} files", files.len());
+    }
+    if let [source] = files.as_slice() {
+        return run_file(&matches, source);
+    }
+
+    // When processing many files an error with one of them doesn't stop the others
+    let mut failed = 0;
+    for source in &files {
+        println!("==> {} <==", source.display());
+        if let Err(e) = run_file(&matches, source) {
+            print_error(&e);
+            failed += 1;
+        }
+    }
+    if failed > 0 {
+        bail!("{} of {} files failed", failed, files.len());
+    }
+
+    Ok(())
+}
+
+// Analyze, compress or decompress the single file `source`
+fn run_file(matches: &ArgMatches, source: &Path) -> Result<()> {
     if !source.exists() {
-        bail!("File '{}' does not exist", source_filename);
+        bail!("File '{}' does not exist", source.display());
+    }
+    let format = input::detect_format(source)?;
+    if let Some(directory) = matches.get_one::<String>("split") {
+        if format == Format::Wazm {
+            bail!("File '{}' is compressed, decompress it before splitting", source.display());
+        }
+        let buf = input::read_input(source, false)?;
+        wazm::split::split(&Module::from_source(source, &buf)?, Path::new(directory))?;
+        return Ok(());
     }
-    if source.extension() != Some("wasm".as_ref()) &&
-        source.extension() != Some("wz".as_ref()) {
-        bail!("File '{}' does not have a .wasm nor .wz extension", source_filename);
+    if format == Format::Wat && !matches.get_flag("analyze") {
+        bail!("File '{}' is WebAssembly text, that can only be analyzed", source.display());
     }
 
     if matches.get_flag("analyze") {
-        let buf: Vec<u8> = std::fs::read(source)?;
-        let module = Module::parse(source, &buf)?;
-        let analysis = wazm::analyze(&module,
+        let color = color::use_color(matches.get_one::<String>("color")
+            .map(|choice| choice.as_str()).unwrap_or("auto"));
+        let mmap = matches.get_flag("mmap");
+        let buf = input::read_input(source, mmap)?;
+        let module = if matches.get_flag("strict") {
+            Module::parse_validated(source, &buf)?
+        } else {
+            Module::from_source(source, &buf)?
+        };
+        if let Some(other_filename) = matches.get_one::<String>("diff") {
+            let other = Path::new(other_filename);
+            let other_buf = input::read_input(other, mmap)?;
+            let other_module = Module::from_source(other, &other_buf)?;
+            let before = wazm::analyze(&module, true, false, false, false)?;
+            let after = wazm::analyze(&other_module, true, false, false, false)?;
+            print_output(&SectionSizeDiff { before: &before, after: &after }.to_string(), color);
+            return Ok(());
+        }
+
+        let mut analysis = wazm::analyze_with_jobs(&module,
                                      matches.get_flag("analyze-sections"),
                                      matches.get_flag("analyze-functions"),
                                      matches.get_flag("analyze-operators"),
                                      matches.get_flag("analyze-call-tree"),
+                                     matches.get_one::<usize>("jobs").copied().unwrap_or(1),
         )?;
-        println!("{}", module);
-        println!("{}", analysis);
+        analysis.top = matches.get_one::<usize>("top").copied();
+        if matches.get_flag("demangle") {
+            analysis.demangle_names();
+        }
+        analysis.hash(&buf);
+        if let Some(section_types) = matches.get_many::<String>("section") {
+            analysis.filter_sections(&section_types.cloned().collect::<Vec<String>>())?;
+        }
+        match matches.get_one::<String>("sort-sections").map(|sort| sort.as_str()) {
+            Some("size") => analysis.sort_sections(SectionSort::Size),
+            Some("type") => analysis.sort_sections(SectionSort::Type),
+            _ => {},
+        }
+        if let Some(function) = matches.get_one::<String>("function") {
+            analysis.select_function(function)?;
+        }
+
+        if matches.get_flag("summary") {
+            print!("{}", analysis.summary());
+            return Ok(());
+        }
+
+        if let Some(max_size) = matches.get_one::<usize>("max-function-size") {
+            let oversized = analysis.oversized_functions(*max_size);
+            for (index, name, size) in &oversized {
+                let line = format!(" {:#5} {:<40}{:#8}", index, name, size);
+                println!("{}", if color { color::warning(&line) } else { line });
+            }
+            if !oversized.is_empty() {
+                bail!("{} function(s) larger than the maximum size of {} bytes", oversized.len(),
+                    max_size);
+            }
+            return Ok(());
+        }
+
+        if let Some(function) = matches.get_one::<String>("disassemble") {
+            let index = analysis.resolve_function(function)?;
+            print!("{}", wazm::disassemble::disassemble(&module, index)?);
+            return Ok(());
+        }
+
+        if matches.get_flag("json") {
+            println!("{}", serde_json::to_string_pretty(&analysis)?);
+            return Ok(());
+        }
+
+        if matches.get_flag("dot") {
+            print!("{}", analysis.to_dot());
+            return Ok(());
+        }
+
+        if matches.get_flag("csv") {
+            print!("{}", analysis.to_csv());
+            return Ok(());
+        }
 
-        let unaccounted_for = module.file_size - analysis.sections_size_total as u64;
-        if unaccounted_for != 0 {
-            println!("Bytes unaccounted for: {}", unaccounted_for);
+        if matches.get_flag("markdown") {
+            print!("{}", analysis.to_markdown());
+            return Ok(());
+        }
+
+        print_output(&format!("{}\n", module), color);
+        print_output(&format!("{}\n", analysis), color);
+
+        if let Some(index) = matches.get_one::<usize>("callers-of") {
+            print_output(&format!("{}\n", Callers { analysis: &analysis, index: *index }), color);
+        }
+
+        // the sections of a ".wat" file are those of the WASM compiled from its text
+        let sections_size_total = analysis.sections_size_total as u64;
+        match module.file_size.checked_sub(sections_size_total) {
+            _ if format == Format::Wat => {},
+            Some(0) => {},
+            Some(unaccounted_for) => println!("Bytes unaccounted for: {}", unaccounted_for),
+            None => println!("Bytes over-counted: {}", sections_size_total - module.file_size),
+        }
+    } else if format == Format::Wasm {
+        let destination_filename = format!("{}.wz", source.display());
+        let destination = output_path(matches)?.unwrap_or(Path::new(&destination_filename));
+        let level = match matches.get_one::<String>("level") {
+            Some(level) => wazm::compress::parse_level(level)?,
+            None => wazm::compress::DEFAULT_LEVEL,
+        };
+        let strip = if matches.get_flag("strip") {
+            Strip::All
+        } else if matches.get_flag("strip-debug") {
+            Strip::Debug
+        } else {
+            Strip::Nothing
+        };
+        let to_stdout = matches.get_flag("stdout");
+        if strip != Strip::Nothing && !to_stdout {
+            let buf: Vec<u8> = std::fs::read(source)?;
+            let module = Module::from_source(source, &buf)?;
+            for name in wazm::compress::stripped_sections(&module, strip) {
+                println!("Stripping custom section '{}'", name);
+            }
+        }
+        let optimize = Optimize {
+            strip_unused: matches.get_flag("optimize-strip-unused"),
+            deduplicate_types: matches.get_flag("optimize"),
+        };
+        let reference = read_reference(matches)?;
+        let output = wazm::compress::compress_bytes_with_reference(&std::fs::read(source)?,
+            reference.as_deref(), level, strip, optimize)?;
+        if to_stdout {
+            std::io::stdout().write_all(&output)?;
+            return Ok(());
+        }
+        std::fs::write(destination, &output)?;
+        if matches.get_flag("verify") {
+            wazm::decompress::verify(source, destination, strip, optimize, reference.as_deref())?;
+            println!("Verified '{}'", destination.display());
+        }
+        if !matches.get_flag("keep") {
+            std::fs::remove_file(source)?;
         }
-    } else if source.extension() == Some("wasm".as_ref()) {
-        let destination_filename = format!("{source_filename}.wz");
-        let destination = Path::new(&destination_filename);
-        wazm::compress(source, destination)?;
     } else {
-        let destination_filename = source.with_extension("");
-        let destination = Path::new(&destination_filename);
-        wazm::decompress(source, destination)?;
+        let check_hash = !matches.get_flag("no-verify");
+        let reference = read_reference(matches)?;
+        let output = wazm::decompress::decompress_bytes_with_reference(&std::fs::read(source)?,
+            reference.as_deref(), check_hash)?;
+        if matches.get_flag("stdout") {
+            std::io::stdout().write_all(&output)?;
+            return Ok(());
+        }
+        let destination_filename = if source.extension() == Some("wz".as_ref()) {
+            source.with_extension("")
+        } else {
+            PathBuf::from(format!("{}.wasm", source.display()))
+        };
+        let destination = output_path(matches)?.unwrap_or(&destination_filename);
+        std::fs::write(destination, &output)?;
+        if !matches.get_flag("keep") {
+            std::fs::remove_file(source)?;
+        }
     }
 
     Ok(())
 }
 
+// Read the reference module given with "--reference", if there is one
+fn read_reference(matches: &ArgMatches) -> Result<Option<Vec<u8>>> {
+    match matches.get_one::<String>("reference") {
+        Some(reference) => Ok(Some(std::fs::read(reference)
+            .chain_err(|| format!("Could not read reference module '{}'", reference))?)),
+        None => Ok(None),
+    }
+}
+
+// Get the destination path given with "--output", checking the directory it is in exists
+fn output_path(matches: &ArgMatches) -> Result<Option<&Path>> {
+    let output = match matches.get_one::<String>("output") {
+        Some(output) => Path::new(output),
+        None => return Ok(None),
+    };
+    if let Some(directory) = output.parent() {
+        if !directory.as_os_str().is_empty() && !directory.is_dir() {
+            bail!("The directory '{}' of the output file does not exist", directory.display());
+        }
+    }
+    Ok(Some(output))
+}
+
 // Parse the command line arguments using clap
 fn get_matches() -> ArgMatches {
     let app = Command::new(env!("CARGO_PKG_NAME"))
@@ -89,17 +318,59 @@ fn get_matches() -> ArgMatches {
             .number_of_values(1)
             .value_name("VERBOSITY_LEVEL")
             .help("Set verbosity level for output (trace, debug, info, warn, default: error)"))
+        .arg(Arg::new("color")
+            .long("color")
+            .num_args(1)
+            .value_name("WHEN")
+            .value_parser(["auto", "always", "never"])
+            .help("Color the analysis output (auto, always or never, default: auto)"))
         .arg(Arg::new("analyze")
             .short('a')
             .long("analyze")
             .action(clap::ArgAction::SetTrue)
             .help("Analyze the WASM file"))
+        .arg(Arg::new("strict")
+            .long("strict")
+            .requires("analyze")
+            .action(clap::ArgAction::SetTrue)
+            .help("Fully validate the WASM file before analyzing it"))
         .arg(Arg::new("analyze-sections")
             .short('s')
             .long("analyze-sections")
             .requires("analyze")
             .action(clap::ArgAction::SetTrue)
             .help("Analyze the Sections of the WASM file"))
+        .arg(Arg::new("section")
+            .long("section")
+            .requires("analyze-sections")
+            .action(clap::ArgAction::Append)
+            .value_name("TYPE")
+            .help("Restrict the Sections analysis to Sections of TYPE, can be repeated"))
+        .arg(Arg::new("summary")
+            .long("summary")
+            .requires("analyze")
+            .conflicts_with_all(["json", "dot", "csv", "markdown"])
+            .action(clap::ArgAction::SetTrue)
+            .help("Print a condensed summary of the analysis instead of the full report"))
+        .arg(Arg::new("mmap")
+            .long("mmap")
+            .requires("analyze")
+            .action(clap::ArgAction::SetTrue)
+            .help("Memory-map the WASM file to analyze it, instead of reading it into memory"))
+        .arg(Arg::new("jobs")
+            .long("jobs")
+            .requires("analyze")
+            .num_args(1)
+            .value_name("N")
+            .value_parser(clap::value_parser!(usize))
+            .help("Analyze the Functions in parallel on N threads (default: 1)"))
+        .arg(Arg::new("sort-sections")
+            .long("sort-sections")
+            .requires("analyze-sections")
+            .num_args(1)
+            .value_name("ORDER")
+            .value_parser(["offset", "size", "type"])
+            .help("Order the Sections by offset, size or type (default: offset)"))
         .arg(Arg::new("analyze-functions")
             .short('f')
             .long("analyze-functions")
@@ -119,11 +390,165 @@ fn get_matches() -> ArgMatches {
             .requires("analyze-functions")
             .action(clap::ArgAction::SetTrue)
             .help("Analyze the Operators used in the WASM file"))
-        .arg(Arg::new("wasm-file")
+        .arg(Arg::new("function")
+            .long("function")
+            .requires("analyze-functions")
+            .num_args(1)
+            .value_name("NAME_OR_INDEX")
+            .help("Restrict the per-function analysis to the Function with this name or index"))
+        .arg(Arg::new("disassemble")
+            .long("disassemble")
+            .requires("analyze-functions")
+            .conflicts_with_all(["json", "dot", "csv", "markdown"])
+            .num_args(1)
+            .value_name("NAME_OR_INDEX")
+            .help("Print the operators of the Function with this name or index"))
+        .arg(Arg::new("top")
+            .long("top")
+            .requires("analyze-functions")
             .num_args(1)
-            .help("the file path of the wasm file to compress/decompress"));
+            .value_name("N")
+            .value_parser(clap::value_parser!(usize))
+            .help("Limit the per-function size and complexity reports to the N largest entries"))
+        .arg(Arg::new("demangle")
+            .long("demangle")
+            .requires("analyze")
+            .action(clap::ArgAction::SetTrue)
+            .help("Demangle Rust and C++ function names in the analysis"))
+        .arg(Arg::new("max-function-size")
+            .long("max-function-size")
+            .requires("analyze-functions")
+            .conflicts_with_all(["json", "dot", "csv", "markdown"])
+            .num_args(1)
+            .value_name("BYTES")
+            .value_parser(clap::value_parser!(usize))
+            .help("List the Functions larger than BYTES, failing if there are any"))
+        .arg(Arg::new("dot")
+            .long("dot")
+            .requires("analyze-functions")
+            .requires("analyze-call-tree")
+            .conflicts_with("json")
+            .action(clap::ArgAction::SetTrue)
+            .help("Output the call-tree of Functions in Graphviz DOT format"))
+        .arg(Arg::new("diff")
+            .long("diff")
+            .requires("analyze")
+            .num_args(1)
+            .value_name("OTHER_WASM_FILE")
+            .help("Compare the sizes of the sections of the WASM file with those of another"))
+        .arg(Arg::new("callers-of")
+            .long("callers-of")
+            .requires("analyze-functions")
+            .num_args(1)
+            .value_name("INDEX")
+            .value_parser(clap::value_parser!(usize))
+            .help("Show the chain of functions that call the Function with index INDEX"))
+        .arg(Arg::new("json")
+            .short('j')
+            .long("json")
+            .requires("analyze")
+            .action(clap::ArgAction::SetTrue)
+            .help("Output the analysis as JSON"))
+        .arg(Arg::new("csv")
+            .long("csv")
+            .requires("analyze-sections")
+            .conflicts_with("json")
+            .action(clap::ArgAction::SetTrue)
+            .help("Output the analysis of Sections as CSV"))
+        .arg(Arg::new("markdown")
+            .long("markdown")
+            .requires("analyze")
+            .conflicts_with_all(["json", "dot", "csv"])
+            .action(clap::ArgAction::SetTrue)
+            .help("Output the analysis of Sections and Functions as Markdown tables"))
+        .arg(Arg::new("split")
+            .long("split")
+            .num_args(1)
+            .value_name("DIR")
+            .conflicts_with_all(["analyze", "output", "stdout"])
+            .help("Split the WASM module into one file per section in DIR, named \
+                   NN_SectionType.bin"))
+        .arg(Arg::new("join")
+            .long("join")
+            .num_args(2)
+            .value_names(["DIR", "OUTPUT"])
+            .conflicts_with_all(["analyze", "split", "output", "stdout"])
+            .help("Join the section files in DIR written by --split into the WASM file OUTPUT"))
+        .arg(Arg::new("level")
+            .short('l')
+            .long("level")
+            .num_args(1)
+            .value_name("LEVEL")
+            .conflicts_with("analyze")
+            .help("Set the compression level (0-9, fast, default or best, default: default)"))
+        .arg(Arg::new("strip")
+            .long("strip")
+            .conflicts_with("analyze")
+            .action(clap::ArgAction::SetTrue)
+            .help("Strip all custom sections when compressing"))
+        .arg(Arg::new("strip-debug")
+            .long("strip-debug")
+            .conflicts_with("analyze")
+            .conflicts_with("strip")
+            .action(clap::ArgAction::SetTrue)
+            .help("Strip the debug and name custom sections when compressing"))
+        .arg(Arg::new("optimize")
+            .long("optimize")
+            .conflicts_with("analyze")
+            .action(clap::ArgAction::SetTrue)
+            .help("Remove duplicate types from the module when compressing"))
+        .arg(Arg::new("optimize-strip-unused")
+            .long("optimize-strip-unused")
+            .conflicts_with("analyze")
+            .action(clap::ArgAction::SetTrue)
+            .help("Remove functions that can't be reached from the exports, start function or \
+                   tables when compressing"))
+        .arg(Arg::new("reference")
+            .long("reference")
+            .num_args(1)
+            .value_name("REFERENCE")
+            .conflicts_with("analyze")
+            .help("Only store the sections that differ from the WASM module REFERENCE when \
+                   compressing. The same REFERENCE is needed to decompress"))
+        .arg(Arg::new("verify")
+            .long("verify")
+            .conflicts_with("analyze")
+            .action(clap::ArgAction::SetTrue)
+            .help("Verify the compressed file decompresses to an equivalent WASM module"))
+        .arg(Arg::new("no-verify")
+            .long("no-verify")
+            .conflicts_with("analyze")
+            .action(clap::ArgAction::SetTrue)
+            .help("Skip checking the decompressed module against the hash in the wazm file"))
+        .arg(Arg::new("output")
+            .long("output")
+            .conflicts_with("analyze")
+            .conflicts_with("stdout")
+            .num_args(1)
+            .value_name("PATH")
+            .help("Write the compressed or decompressed file to PATH instead of next to the \
+                   source file"))
+        .arg(Arg::new("keep")
+            .short('k')
+            .long("keep")
+            .conflicts_with("analyze")
+            .action(clap::ArgAction::SetTrue)
+            .help("Keep the source file. Without this the source file is deleted once the \
+                   compressed or decompressed file has been written, and verified if --verify \
+                   is used"))
+        .arg(Arg::new("stdout")
+            .long("stdout")
+            .conflicts_with("analyze")
+            .conflicts_with("verify")
+            .action(clap::ArgAction::SetTrue)
+            .help("Write the compressed or decompressed file to stdout instead of to a file"))
+        .arg(Arg::new("wasm-file")
+            .required_unless_present("join")
+            .conflicts_with("join")
+            .num_args(1..)
+            .help("the file paths of the wasm files to compress/decompress, or directories \
+                   or glob patterns to find them in. Each file is deleted after it is \
+                   compressed/decompressed unless --keep is used"));
 
-    // TODO add an option to validate contents are equivalent after compressing by
-    // decompressing, parsing and then comparing
     app.get_matches()
 }
\ No newline at end of file
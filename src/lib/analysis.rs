@@ -7,73 +7,452 @@ use wasmparser::ExternalKind;
 use wasmparser::ElementSectionReader;
 use wasmparser::ElementItems::*;
 use wasmparser::TypeRef;
+use wasmparser::TableSectionReader;
+use wasmparser::GlobalSectionReader;
+use wasmparser::DataSectionReader;
+use wasmparser::DataKind;
+use wasmparser::GlobalType;
+use wasmparser::ConstExpr;
+use wasmparser::HeapType;
+use wasmparser::ValType;
+use wasmparser::TypeSectionReader;
+use wasmparser::FunctionSectionReader;
+use wasmparser::Type;
+use wasmparser::FuncType;
+use wasmparser::BlockType;
+use wasmparser::TableType;
+use wasmparser::MemorySectionReader;
+use wasmparser::MemoryType;
 use wasmparser::RefType;
 use wasmparser::Operator;
+use wasmparser::CustomSectionReader;
+use wasmparser::NameSectionReader;
+use wasmparser::ProducersSectionReader;
+use wasmparser::Name;
 use core::ops::Range;
 use std::fmt;
 use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::collections::HashSet;
 use std::ops::RangeInclusive;
 use leb128;
+use rayon::prelude::*;
+use serde::{Serialize, Serializer};
+use sha2::{Digest, Sha256};
 
 use crate::Module;
+use crate::parse::AsModule;
+use crate::parse::ModuleKind;
 
+#[derive(Serialize)]
 pub struct Section {
     section_type: String,
     header_location: usize,
     item_count: Option<u32>,
     range: Range<usize>,
     size: usize,
+    percentage: f64, // of the total size of all sections, computed once all are added
 }
 
 impl Section {
     fn header(f: &mut fmt::Formatter) -> fmt::Result {
-        writeln!(f, "Header Start     Content Start    Content End     Size (HEX)    Size    Type               Items")
+        writeln!(f, "Header Start     Content Start    Content End     Size (HEX)    Size       %    Type               Items")
     }
 }
 
 impl fmt::Display for Section {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:#014x} : {:#014x} - {:#014x}{:#10x}{:#10}{:7.1}%  {:<18}",
+               self.header_location,
+               self.range.start,
+               self.range.end - 1,
+               self.size,
+               self.size,
+               self.percentage,
+               self.section_type)?;
         match self.item_count {
-            Some(count) => write!(f, "{:#014x} : {:#014x} - {:#014x}{:#10x}{:#10}  {:<18}{:#8}",
-                                  self.header_location,
-                                  self.range.start,
-                                  self.range.end - 1,
-                                  self.size,
-                                  self.size,
-                                  self.section_type,
-                                  count),
-            None => write!(f, "{:#014} : {:#014x} - {:#014x}{:#10x}{:#10}  {:<18}",
-                           "",
-                           self.range.start,
-                           self.range.end - 1,
-                           self.size,
-                           self.size,
-                           self.section_type),
+            Some(count) => write!(f, "{:#8}", count),
+            None => write!(f, "{:8}", ""),
         }
     }
 }
 
+/// The names used for the type of each [Section]
+pub const SECTION_TYPES: [&str; 27] = [
+    "Version", "TypeSection", "ImportSection", "FunctionSection", "TableSection",
+    "MemorySection", "TagSection", "GlobalSection", "ExportSection", "StartSection",
+    "ElementSection", "DataCountSection", "CodeSectionStart", "DataSection", "CustomSection",
+    "UnknownSection", "ModuleSection", "InstanceSection", "CoreTypeSection", "ComponentSection",
+    "ComponentInstanceSection", "ComponentAliasSection", "ComponentTypeSection",
+    "ComponentCanonicalSection", "ComponentStartSection", "ComponentImportSection",
+    "ComponentExportSection",
+];
+
+/// The order to list the sections of a module in
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SectionSort {
+    /// In the order they appear in the file
+    Offset,
+    /// Largest first
+    Size,
+    /// Alphabetically by section type
+    Type,
+}
+
+/// Details of a table declared in, or imported into, a wasm module
+#[derive(Serialize)]
+pub struct TableInfo {
+    #[serde(serialize_with = "serialize_display")]
+    pub element_type: RefType,
+    pub initial: u32,
+    pub maximum: Option<u32>,
+    pub imported: bool,
+}
+
+impl TableInfo {
+    fn new(table_type: &TableType, imported: bool) -> Self {
+        TableInfo {
+            element_type: table_type.element_type,
+            initial: table_type.initial,
+            maximum: table_type.maximum,
+            imported,
+        }
+    }
+}
+
+/// Details of a global declared in, or imported into, a wasm module
+#[derive(Serialize)]
+pub struct GlobalInfo {
+    #[serde(serialize_with = "serialize_display")]
+    pub content_type: ValType,
+    pub mutable: bool,
+    pub initializer: Option<String>, // the constant expression, None for an imported global
+}
+
+impl GlobalInfo {
+    fn new(global_type: &GlobalType, initializer: Option<String>) -> Self {
+        GlobalInfo {
+            content_type: global_type.content_type,
+            mutable: global_type.mutable,
+            initializer,
+        }
+    }
+}
+
+impl fmt::Display for GlobalInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.content_type)?;
+        if self.mutable {
+            write!(f, " mut")?;
+        }
+        match &self.initializer {
+            Some(initializer) => write!(f, " = {}", initializer),
+            None => write!(f, " (imported)"),
+        }
+    }
+}
+
+// Render a constant expression as text, e.g. "i32.const 1048576" or "global.get 0"
+fn render_const_expr(expr: &ConstExpr) -> Result<String> {
+    let mut parts = vec![];
+    let mut reader = expr.get_operators_reader();
+    while !reader.eof() {
+        let part = match reader.read()? {
+            Operator::End => continue,
+            Operator::I32Const { value } => format!("i32.const {}", value),
+            Operator::I64Const { value } => format!("i64.const {}", value),
+            Operator::F32Const { value } => format!("f32.const {}", f32::from_bits(value.bits())),
+            Operator::F64Const { value } => format!("f64.const {}", f64::from_bits(value.bits())),
+            Operator::V128Const { value } => format!("v128.const {:#034x}", value.i128()),
+            Operator::GlobalGet { global_index } => format!("global.get {}", global_index),
+            Operator::RefFunc { function_index } => format!("ref.func {}", function_index),
+            Operator::RefNull { hty: HeapType::Func } => "ref.null func".into(),
+            Operator::RefNull { hty: HeapType::Extern } => "ref.null extern".into(),
+            operator => operator_name(&operator).to_string(),
+        };
+        parts.push(part);
+    }
+    Ok(parts.join(" "))
+}
+
+impl fmt::Display for TableInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} initial: {}", self.element_type, self.initial)?;
+        if let Some(maximum) = self.maximum {
+            write!(f, " maximum: {}", maximum)?;
+        }
+        if self.imported {
+            write!(f, " (imported)")?;
+        }
+        Ok(())
+    }
+}
+
+/// Counts of each kind of import from one module namespace, e.g. "wasi_snapshot_preview1"
+#[derive(Default, Serialize)]
+pub struct ImportGroup {
+    pub functions: usize,
+    pub memories: usize,
+    pub globals: usize,
+    pub tables: usize,
+    pub tags: usize,
+}
+
+impl fmt::Display for ImportGroup {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let counts = [(self.functions, "function", "functions"),
+            (self.memories, "memory", "memories"), (self.globals, "global", "globals"),
+            (self.tables, "table", "tables"), (self.tags, "tag", "tags")];
+        let counts: Vec<String> = counts.iter()
+            .filter(|(count, _, _)| *count > 0)
+            .map(|(count, singular, plural)|
+                format!("{} {}", count, if *count == 1 { singular } else { plural }))
+            .collect();
+        write!(f, "{}", counts.join(", "))
+    }
+}
+
+// Serialize a type that has no [Serialize] implementation using its [fmt::Display] form
+fn serialize_display<T: fmt::Display, S: Serializer>(value: &T, serializer: S)
+    -> std::result::Result<S::Ok, S::Error> {
+    serializer.collect_str(value)
+}
+
+// Format a function type as a signature, e.g. "(i32, i32) -> i32"
+fn signature(func_type: &FuncType) -> String {
+    let join = |types: &[wasmparser::ValType]| types.iter().map(|t| t.to_string())
+        .collect::<Vec<String>>().join(", ");
+    match func_type.results() {
+        [] => format!("({})", join(func_type.params())),
+        [result] => format!("({}) -> {}", join(func_type.params()), result),
+        results => format!("({}) -> ({})", join(func_type.params()), join(results)),
+    }
+}
+
+// Define `operator_name()` with a match arm for every operator wasmparser knows about, so that
+// each operator is named by its variant in the [Operator] enum, whatever fields it has
+macro_rules! define_operator_name {
+    ($( @$proposal:ident $op:ident $({ $($payload:tt)* })? => $visit:ident)*) => {
+        // Get the name of an operator, e.g. "I32Add" or "LocalGet"
+        fn operator_name(operator: &Operator) -> &'static str {
+            match operator {
+                $( Operator::$op { .. } => stringify!($op), )*
+            }
+        }
+    }
+}
+
+wasmparser::for_each_operator!(define_operator_name);
+
+/// The fields of a producers section, each with the (name, version) of the tools recorded in it
+pub type ProducerFields = Vec<(String, Vec<(String, String)>)>;
+
 /// Analysis results of a wasm module
-#[derive(Default)]
+#[derive(Default, Serialize)]
 pub struct Analysis {
+    pub module_kind: ModuleKind,
+    pub version: u16,
+    pub file_size: u64,
+    pub module_hash: String, // hex SHA-256 of the whole file, when set with [Analysis::hash]
     pub include_functions: bool,
     pub implemented_function_count: u64,
     pub imported_functions: BTreeMap<usize, String>,
     pub exported_functions: BTreeMap<usize, String>,
+    pub function_names: BTreeMap<usize, String>,
+    pub producers: ProducerFields,
+    pub function_sizes: Vec<(usize, usize)>, // (function index, size of body in bytes)
+    pub function_locals: Vec<(usize, u32)>, // (function index, number of locals declared)
+    pub function_complexity: BTreeMap<usize, u32>, // function index --> cyclomatic complexity
+    pub br_table_targets: Vec<usize>, // number of targets of each br_table operator
+    pub control_flow_stats: BTreeMap<String, u64>, // control flow operator --> count
+    pub dead_instruction_count: u64, // operators that follow an unconditional branch in a block
+    pub call_indirect_sites: Vec<(usize, u32, u32)>, // (caller index, type index, table index)
 
     pub include_function_call_tree: bool,
     pub static_function_calls: HashMap<usize, Vec<usize>>, // index of caller --> vector of indexes called
     pub dynamic_dispatch_functions: Vec<usize>,
+    pub statically_called_functions: Vec<usize>,
+    pub uncalled_functions: Vec<usize>,
+    pub unused_imports: Vec<usize>,
+    pub import_callers: BTreeMap<usize, Vec<usize>>, // index of import --> indexes of callers
+    pub start_function: Option<usize>,
+    pub entry_point: Option<(usize, String)>, // (function index, name) of `_start` or `main` export
+    pub other_entry_point: Option<(usize, String)>, // `main` when `_start` is also exported
+    pub max_call_depth: usize, // longest chain of calls, with each cycle of calls as one level
+    pub recursive_functions: Vec<usize>,
+    pub top: Option<usize>, // limit per-function reports to this many of the largest functions
     pub include_sections: bool,
     pub sections: Vec<Section>,
     pub sections_size_total: usize,
+    pub sections_filtered: bool, // only some types of sections are in `sections`
+    pub largest_section: Option<(String, usize)>, // (type, size of contents), tracked always
+    pub tables: Vec<TableInfo>,
+    pub globals: Vec<GlobalInfo>,
+    pub active_data_segments: u32,
+    pub active_data_size: usize,
+    pub passive_data_segments: Vec<(u32, usize)>, // (data segment index, size in bytes)
+    pub memory_init_segments: BTreeSet<u32>, // data segments initialized by memory.init
+    pub import_groups: BTreeMap<String, ImportGroup>, // import module namespace --> counts
+    pub import_totals: ImportGroup, // counts of imports of each kind from all namespaces
+    pub export_count: u32,
+    pub imported_memories: BTreeMap<usize, String>,
+    pub imported_globals: BTreeMap<usize, String>,
+    pub imported_tables: BTreeMap<usize, String>,
+    pub exported_memories: BTreeMap<usize, Vec<String>>, // index --> names it is exported as
+    pub exported_globals: BTreeMap<usize, Vec<String>>,
+    pub exported_tables: BTreeMap<usize, Vec<String>>,
+
+    #[serde(skip)]
+    pub types: Vec<Type>,
+    pub function_types: Vec<u32>, // function index --> type index
+    pub duplicate_type_count: usize,
+    pub duplicate_types: Vec<(usize, usize)>, // (index of duplicate type, index of first definition)
 
     pub include_operators: bool,
     pub operator_usage: BTreeMap<String, u64>,
     pub sorted_operator_usage: Vec<(String, u64)>,
     pub operator_count: u64,
+    #[serde(skip)]
+    pub function_operator_usage: BTreeMap<usize, BTreeMap<String, u64>>,
+    pub selected_function: Option<usize>,
+
+    pub uses_simd: bool,
+    pub simd_operator_count: u64,
+    pub uses_relaxed_simd: bool,
+    pub relaxed_simd_operator_count: u64,
+    pub uses_bulk_memory: bool,
+    pub bulk_memory_usage: BTreeMap<String, u64>,
+    pub uses_threads: bool,
+    pub uses_shared_memory: bool, // a declared or imported memory is shared
+    pub atomic_operators: BTreeSet<String>,
+    pub uses_multi_value: bool,
+    pub uses_reference_types: bool,
+    pub reference_type_operator_count: u64,
+    pub uses_memory_grow: bool,
+    pub memory_grow_sites: BTreeMap<usize, u32>, // function index --> number of memory.grow
+    pub memory_size_count: u64,
+    pub uses_sign_extension: bool,
+    pub sign_extension_operator_count: u64,
+    pub uses_nontrapping_float_to_int: bool,
+    pub nontrapping_float_to_int_count: u64,
+    pub uses_floating_point: bool,
+    pub uses_memory64: bool,
+    pub uses_tail_calls: bool,
+    pub tail_call_count: u64,
+    pub uses_exceptions: bool,
+    pub tag_count: u32,
+    pub exception_operator_count: u64,
+    pub floating_point_operator_count: u64,
+    pub uses_gc: bool,
+    pub gc_features: BTreeSet<String>, // the parts of the GC proposal used
+    pub wasi: Option<String>, // the WASI ABI the module imports from, if any
+    pub section_order_warnings: Vec<String>,
+    pub has_debug_info: bool,
+    pub debug_sections: Vec<(String, usize)>, // (name, size) of each DWARF custom section
+    pub debug_info_size: usize,
+}
+
+// Ids of the known core module sections in the order they must appear in a module.
+// Custom sections may appear anywhere so are not included
+const SECTION_ORDER: [u8; 13] = [1, 2, 3, 4, 5, 13, 6, 7, 8, 9, 12, 10, 11];
+
+// Name of a known core module section from its id
+fn section_name(id: u8) -> &'static str {
+    match id {
+        1 => "TypeSection",
+        2 => "ImportSection",
+        3 => "FunctionSection",
+        4 => "TableSection",
+        5 => "MemorySection",
+        6 => "GlobalSection",
+        7 => "ExportSection",
+        8 => "StartSection",
+        9 => "ElementSection",
+        10 => "CodeSection",
+        11 => "DataSection",
+        12 => "DataCountSection",
+        13 => "TagSection",
+        _ => "UnknownSection",
+    }
+}
+
+// Demangle a Rust (legacy or v0) or C++ symbol, returning other names unchanged
+fn demangle(name: &str) -> String {
+    if let Ok(demangled) = rustc_demangle::try_demangle(name) {
+        // the alternate format omits the hash from legacy Rust symbols
+        return format!("{:#}", demangled);
+    }
+    cpp_demangle::Symbol::new(name).ok()
+        .and_then(|symbol| symbol.demangle().ok())
+        .unwrap_or_else(|| name.to_owned())
+}
+
+// Tracks whether operators of a function body can be reached, as after an unconditional
+// branch the rest of the enclosing block can't be, until its Else or End
+#[derive(Default)]
+struct Reachability {
+    depth: usize,
+    dead_from: Option<usize>, // the depth of the block that became unreachable
+}
+
+impl Reachability {
+    // Update the state with the next operator, returning true if it is unreachable
+    fn is_dead(&mut self, operator: &Operator) -> bool {
+        match operator {
+            Operator::Block { .. } | Operator::Loop { .. } | Operator::If { .. } |
+            Operator::Try { .. } => {
+                self.depth += 1;
+                self.dead_from.is_some()
+            },
+            Operator::End | Operator::Delegate { .. } => {
+                let dead = self.dead_from.is_some_and(|depth| depth < self.depth);
+                if self.dead_from == Some(self.depth) {
+                    self.dead_from = None;
+                }
+                self.depth = self.depth.saturating_sub(1);
+                dead
+            },
+            Operator::Else | Operator::Catch { .. } | Operator::CatchAll => {
+                if self.dead_from == Some(self.depth) {
+                    self.dead_from = None;
+                }
+                self.dead_from.is_some()
+            },
+            _ if self.dead_from.is_some() => true,
+            Operator::Unreachable | Operator::Br { .. } | Operator::BrTable { .. } |
+            Operator::Return | Operator::ReturnCall { .. } |
+            Operator::ReturnCallIndirect { .. } | Operator::Throw { .. } |
+            Operator::Rethrow { .. } => {
+                self.dead_from = Some(self.depth);
+                false
+            },
+            _ => false,
+        }
+    }
 }
 
+// Operator name prefixes of the fixed-width SIMD proposal
+const SIMD_PREFIXES: [&str; 7] = ["V128", "I8x16", "I16x8", "I32x4", "I64x2", "F32x4", "F64x2"];
+
+// Upper bounds (inclusive) in bytes of the buckets used in the histogram of function sizes.
+// Functions larger than the last bound are counted in a final bucket
+const SIZE_HISTOGRAM_BUCKETS: [usize; 4] = [50, 100, 250, 500];
+
+// Maximum width of a bar in the histogram of function sizes
+const HISTOGRAM_BAR_WIDTH: usize = 50;
+
+// Operators of the bulk-memory proposal
+const BULK_MEMORY_OPERATORS: [&str; 7] = ["MemoryCopy", "MemoryFill", "MemoryInit", "DataDrop",
+    "TableCopy", "TableInit", "ElemDrop"];
+
+// Operators of the sign-extension proposal
+const SIGN_EXTENSION_OPERATORS: [&str; 5] = ["I32Extend8S", "I32Extend16S", "I64Extend8S",
+    "I64Extend16S", "I64Extend32S"];
+
+// Operators that change the flow of control, counted in the control flow statistics
+const CONTROL_FLOW_OPERATORS: [&str; 11] = ["Block", "Loop", "If", "Else", "Br", "BrIf", "BrTable",
+    "Return", "Call", "CallIndirect", "Unreachable"];
+
 impl Analysis {
     fn track_size(&mut self, section_type: &str, range: &Range<usize>) -> Result<usize> {
         let size = range.end - range.start;
@@ -95,6 +474,11 @@ impl Analysis {
 
     fn add_section(&mut self, section_type: &str, item_count: Option<u32>, range: &Range<usize>)
                    -> Result<()> {
+        let size = range.end - range.start;
+        if self.largest_section.as_ref().is_none_or(|(_, largest)| size > *largest) {
+            self.largest_section = Some((section_type.to_owned(), size));
+        }
+
         if self.include_sections {
             let header_size = self.track_size(section_type, range)?;
             let section = Section {
@@ -103,6 +487,7 @@ impl Analysis {
                 item_count,
                 range: range.clone(),
                 size: range.end - range.start,
+                percentage: 0.0,
             };
             self.sections.push(section);
         }
@@ -110,15 +495,17 @@ impl Analysis {
         Ok(())
     }
 
-    fn add_elements(&mut self, elements_reader: &ElementSectionReader) -> Result<()> {
+    fn add_elements(&mut self, module: &Module, elements_reader: &ElementSectionReader)
+        -> Result<()> {
         self.add_section("ElementSection", Some(elements_reader.count()), &elements_reader.range())?;
 
-        for element in elements_reader.clone().into_iter().flatten() {
+        for element in module.section_items(elements_reader)?.flatten() {
             if element.ty == RefType::FUNCREF || element.ty == RefType::FUNC {
                 if let Functions(section) = element.items {
-                    self.dynamic_dispatch_functions = section.into_iter()
-                        .map(|e| e.unwrap() as usize)
-                        .collect::<Vec<usize>>();
+                    // a module may have many element segments, so accumulate across them all
+                    for function_index in section {
+                        self.dynamic_dispatch_functions.push(function_index? as usize);
+                    }
                     self.dynamic_dispatch_functions.sort();
                     self.dynamic_dispatch_functions.dedup();
                 }
@@ -128,260 +515,1603 @@ impl Analysis {
         Ok(())
     }
 
-    fn add_function_call(&mut self, caller_index: usize, called_index: usize) {
-        self.static_function_calls.entry(caller_index)
-            .and_modify(|v| { if !v.contains(&called_index) { v.push(called_index) } })
-            .or_insert(vec!());
-    }
+    fn add_custom(&mut self, reader: &CustomSectionReader) -> Result<()> {
+        self.add_section("CustomSection", None, &reader.range())?;
 
-    fn add_function(&mut self, function_body: &FunctionBody, index: &mut usize) -> Result<()> {
-        if !self.include_functions {
-            return Ok(());
+        match reader.name() {
+            "name" => self.add_names(reader)?,
+            "producers" => self.add_producers(reader),
+            name if name.starts_with(".debug_") => {
+                let size = reader.range().len();
+                self.has_debug_info = true;
+                self.debug_sections.push((name.to_owned(), size));
+                self.debug_info_size += size;
+            },
+            _ => {},
         }
 
-        let mut reader = function_body.get_operators_reader()?;
-        while !reader.eof() {
-            let operator = reader.read()?;
+        Ok(())
+    }
 
-            if let Operator::Call{function_index} = operator {
-                self.add_function_call(*index, function_index as usize);
+    // The producers section is informational only, so a malformed one is ignored
+    fn add_producers(&mut self, reader: &CustomSectionReader) {
+        let read_fields = || -> wasmparser::Result<ProducerFields> {
+            let mut fields = vec![];
+            for field in ProducersSectionReader::new(reader.data(), reader.data_offset())? {
+                let field = field?;
+                let mut values = vec![];
+                for value in field.values {
+                    let value = value?;
+                    values.push((value.name.to_owned(), value.version.to_owned()));
+                }
+                fields.push((field.name.to_owned(), values));
             }
+            Ok(fields)
+        };
 
-            if self.include_operators {
-                let opname = format!("{:?}", operator).split_whitespace().next().unwrap_or("")
-                    .to_string();
-                self.operator_usage.entry(opname)
-                    .and_modify(|count| *count += 1)
-                    .or_insert(1);
-                self.operator_count += 1;
-            }
+        if let Ok(fields) = read_fields() {
+            self.producers = fields;
         }
+    }
 
-        self.implemented_function_count += 1;
-
-        *index += 1;
+    fn add_names(&mut self, reader: &CustomSectionReader) -> Result<()> {
+        for name in NameSectionReader::new(reader.data(), reader.data_offset()) {
+            if let Name::Function(name_map) = name? {
+                for naming in name_map {
+                    let naming = naming?;
+                    self.function_names.insert(naming.index as usize, naming.name.to_owned());
+                }
+            }
+        }
 
         Ok(())
     }
 
-    fn add_exports(&mut self, reader: &ExportSectionReader) -> Result<()> {
-        self.add_section("ExportSection", Some(reader.count()), &reader.range())?;
+    fn add_types(&mut self, module: &Module, reader: &TypeSectionReader) -> Result<()> {
+        self.add_section("TypeSection", Some(reader.count()), &reader.range())?;
 
-        if self.include_functions {
-            for export in reader.clone().into_iter().flatten() {
-                if export.kind == ExternalKind::Func {
-                    self.exported_functions.insert(export.index as usize, export.name.to_owned());
-                }
+        let mut first_definitions: HashMap<FuncType, usize> = HashMap::new();
+        for ty in module.section_items(reader)? {
+            let ty = ty?;
+            let type_index = self.types.len();
+            match &ty {
+                Type::Func(func_type) => {
+                    if func_type.results().len() > 1 {
+                        self.uses_multi_value = true;
+                    }
+
+                    match first_definitions.get(func_type) {
+                        Some(first_index) => {
+                            self.duplicate_types.push((type_index, *first_index));
+                            self.duplicate_type_count += 1;
+                        },
+                        None => {
+                            first_definitions.insert(func_type.clone(), type_index);
+                        },
+                    }
+                },
+                // the wasmparser version used can't read struct types, only array types
+                Type::Array(_) => {
+                    self.uses_gc = true;
+                    self.gc_features.insert("array types".into());
+                },
             }
+            self.types.push(ty);
         }
 
         Ok(())
     }
 
-    fn add_imports(&mut self, reader: &ImportSectionReader, function_index: &mut usize) -> Result<()> {
-        self.add_section("ImportSection", Some(reader.count()), &reader.range())?;
+    fn add_tables(&mut self, module: &Module, reader: &TableSectionReader) -> Result<()> {
+        self.add_section("TableSection", Some(reader.count()), &reader.range())?;
 
-        if self.include_functions {
-            for import in reader.clone().into_iter().flatten() {
-                if matches!(import.ty, TypeRef::Func(_)) {
-                    self.imported_functions.insert(*function_index, import.name.to_owned());
-                    *function_index += 1;
-                }
-            }
+        for table in module.section_items(reader)? {
+            self.add_table(&table?.ty, false);
         }
 
         Ok(())
     }
 
-    fn post_process(&mut self) {
-        // order the operator usage
-        let mut vec: Vec<(String, u64)> = self.operator_usage.iter()
-            .map(|(s, c)| (s.to_string(), *c)).collect();
-        vec.sort_by(|a, b| b.1.cmp(&a.1));
-        self.sorted_operator_usage = vec;
+    fn add_globals(&mut self, module: &Module, reader: &GlobalSectionReader) -> Result<()> {
+        self.add_section("GlobalSection", Some(reader.count()), &reader.range())?;
+
+        for global in module.section_items(reader)? {
+            let global = global?;
+            let initializer = render_const_expr(&global.init_expr)?;
+            self.globals.push(GlobalInfo::new(&global.ty, Some(initializer)));
+        }
+
+        Ok(())
     }
 
-    fn print_called_list(&self, call_chain: Vec<usize>, f: &mut fmt::Formatter) -> fmt::Result {
-        let index = call_chain.last().unwrap_or(&1);
-        if let Some(called_list) = self.static_function_calls.get(index) {
-            let level = call_chain.len();
-            for called in called_list {
-                if call_chain.contains(called) {
-                    writeln!(f, "     {}+- #{} Cyclic call", format_args!("{: >1$}", "", level * 3), called)?;
-                } else {
-                    writeln!(f, "     {}+- #{}", format_args!("{: >1$}", "", level * 3), called)?;
-                    let mut new_chain = call_chain.clone();
-                    new_chain.push(*called);
-                    self.print_called_list(new_chain, f)?;
-                }
+    fn add_data(&mut self, module: &Module, reader: &DataSectionReader) -> Result<()> {
+        self.add_section("DataSection", Some(reader.count()), &reader.range())?;
+
+        for (index, data) in module.section_items(reader)?.enumerate() {
+            let data = data?;
+            match data.kind {
+                DataKind::Active { .. } => {
+                    self.active_data_segments += 1;
+                    self.active_data_size += data.data.len();
+                },
+                DataKind::Passive =>
+                    self.passive_data_segments.push((index as u32, data.data.len())),
             }
         }
+
         Ok(())
     }
 
-    fn print_call_tree(&self, root_index: &usize, name: &str, f: &mut fmt::Formatter) -> fmt::Result {
-        writeln!(f, "\t#{} '{}'", root_index, name)?;
-        self.print_called_list(vec!(*root_index), f)?;
-        writeln!(f)
+    fn add_table(&mut self, table_type: &TableType, imported: bool) {
+        if table_type.element_type.is_extern_ref() {
+            self.uses_reference_types = true;
+        }
+        self.tables.push(TableInfo::new(table_type, imported));
     }
-}
 
-#[derive(PartialEq, Debug, Default)]
-struct RangeVec(Vec<RangeVecEntry>);
-#[derive(PartialEq, Debug)]
-enum RangeVecEntry {
-    RangeEntry(RangeInclusive<usize>),
-    SingleEntry(usize)
-}
+    fn add_function_call(&mut self, caller_index: usize, called_index: usize) {
+        self.static_function_calls.entry(caller_index)
+            .and_modify(|v| { if !v.contains(&called_index) { v.push(called_index) } })
+            .or_insert_with(|| vec![called_index]);
+    }
 
-impl fmt::Display for RangeVec {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "[")?;
-        for entry in &self.0[0..self.0.len() -1] {
-            match entry {
-                RangeVecEntry::RangeEntry(range) =>write!(f, "{}..{}, ",
-                                                          range.start(), range.end())?,
-                RangeVecEntry::SingleEntry(number) => write!(f, "{}, ", number)?,
-            }
+    // Feature detection is done for all functions, whatever sub-analysis was requested, as
+    // it is used to check compatibility of the module with a runtime
+    fn detect_features(&mut self, operator: &Operator, opname: &str) {
+        match operator {
+            Operator::Block { blockty } | Operator::Loop { blockty } | Operator::If { blockty } => {
+                if let BlockType::FuncType(type_index) = blockty {
+                    if let Some(Type::Func(func_type)) = self.types.get(*type_index as usize) {
+                        if func_type.results().len() > 1 {
+                            self.uses_multi_value = true;
+                        }
+                    }
+                }
+            },
+            Operator::I31New | Operator::I31GetS | Operator::I31GetU => {
+                self.uses_gc = true;
+                self.gc_features.insert("i31 references".into());
+            },
+            Operator::MemoryInit { data_index, .. } => {
+                self.memory_init_segments.insert(*data_index);
+            },
+            Operator::ReturnCall { .. } | Operator::ReturnCallIndirect { .. } => {
+                self.uses_tail_calls = true;
+                self.tail_call_count += 1;
+            },
+            Operator::Try { .. } | Operator::Catch { .. } | Operator::CatchAll |
+            Operator::Throw { .. } | Operator::Rethrow { .. } | Operator::Delegate { .. } => {
+                self.uses_exceptions = true;
+                self.exception_operator_count += 1;
+            },
+            Operator::RefNull { .. } | Operator::RefFunc { .. } | Operator::RefIsNull |
+            Operator::TableGet { .. } | Operator::TableSet { .. } | Operator::TableGrow { .. } |
+            Operator::TableSize { .. } | Operator::TableFill { .. } | Operator::TypedSelect { .. } => {
+                self.uses_reference_types = true;
+                self.reference_type_operator_count += 1;
+            },
+            _ => {},
         }
-        match &self.0[self.0.len()-1] {
-            RangeVecEntry::RangeEntry(range) =>write!(f, "{}..{}",
-                                                      range.start(), range.end())?,
-            RangeVecEntry::SingleEntry(number) => write!(f, "{}", number)?,
+
+        if SIMD_PREFIXES.iter().any(|prefix| opname.starts_with(prefix)) {
+            self.uses_simd = true;
+            self.simd_operator_count += 1;
+        }
+
+        // the operators of the relaxed SIMD proposal, e.g. "F32x4RelaxedMadd"
+        if opname.contains("Relaxed") {
+            self.uses_relaxed_simd = true;
+            self.relaxed_simd_operator_count += 1;
+        }
+
+        if BULK_MEMORY_OPERATORS.contains(&opname) {
+            self.uses_bulk_memory = true;
+            self.bulk_memory_usage.entry(opname.to_string())
+                .and_modify(|count| *count += 1)
+                .or_insert(1);
+        }
+
+        if SIGN_EXTENSION_OPERATORS.contains(&opname) {
+            self.uses_sign_extension = true;
+            self.sign_extension_operator_count += 1;
+        }
+
+        // the saturating conversions from floats to integers, e.g. "I32TruncSatF32S"
+        if opname.contains("TruncSat") {
+            self.uses_nontrapping_float_to_int = true;
+            self.nontrapping_float_to_int_count += 1;
+        }
+
+        // arithmetic, comparisons, constants, loads, stores and conversions to or from floats
+        if opname.contains("F32") || opname.contains("F64") {
+            self.uses_floating_point = true;
+            self.floating_point_operator_count += 1;
+        }
+
+        if opname.contains("Atomic") {
+            self.uses_threads = true;
+            self.atomic_operators.insert(opname.to_string());
         }
-        write!(f, "]")
     }
-}
 
-// assumes the input vector is already ordered
-impl From<&Vec<usize>> for RangeVec {
-    fn from(input: &Vec<usize>) -> Self {
-        let mut output: RangeVec = RangeVec::default();
-        let mut start = input[0];
-        let mut end = input[0];
-        for i in input[1..].iter() {
-            if *i != end + 1 {
-                if start == end {
-                    output.0.push(RangeVecEntry::SingleEntry(end));
-                } else {
-                    output.0.push(RangeVecEntry::RangeEntry(start..=end));
-                }
-                start = *i;
-                end = *i;
-            } else {
-                end = *i;
-            }
+    fn add_memory(&mut self, memory_type: &MemoryType) {
+        if memory_type.shared {
+            self.uses_threads = true;
+            self.uses_shared_memory = true;
+        }
+        if memory_type.memory64 {
+            self.uses_memory64 = true;
+        }
+    }
+
+    fn add_tags(&mut self, count: u32) {
+        if count > 0 {
+            self.uses_exceptions = true;
+            self.tag_count += count;
         }
+    }
 
-        if start == end {
-            output.0.push(RangeVecEntry::SingleEntry(end));
-        } else {
-            output.0.push(RangeVecEntry::RangeEntry(start..=end));
+    fn add_memories(&mut self, module: &Module, reader: &MemorySectionReader) -> Result<()> {
+        self.add_section("MemorySection", Some(reader.count()), &reader.range())?;
+
+        for memory in module.section_items(reader)? {
+            self.add_memory(&memory?);
         }
 
-        output
+        Ok(())
     }
-}
 
-impl fmt::Display for Analysis {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        if self.include_sections {
-            writeln!(f, "Sections:")?;
-            Section::header(f)?;
-            for section in &self.sections {
-                writeln!(f, "{}", section)?;
+    fn add_function(&mut self, function_body: &FunctionBody, index: &mut usize) -> Result<()> {
+        let mut locals_count = 0;
+        for locals in function_body.get_locals_reader()? {
+            let (count, val_type) = locals?;
+            if val_type.is_reference_type() {
+                self.uses_reference_types = true;
             }
-
-            writeln!(f, "Total Size: {}", self.sections_size_total)?;
+            locals_count += count;
         }
 
         if self.include_functions {
-            writeln!(f, "\nFunctions:")?;
-            writeln!(f, "Imported Functions ({}):", self.imported_functions.len())?;
-            for (function_index, import_name) in &self.imported_functions {
-                writeln!(f, " {:#5} '{}'", function_index, import_name)?;
-            }
-            writeln!(f, "Implemented ({}):", self.implemented_function_count)?;
-            writeln!(f, "Exported ({}):", self.exported_functions.len())?;
+            let body_range = function_body.range();
+            self.function_sizes.push((*index, body_range.end - body_range.start));
+            self.function_locals.push((*index, locals_count));
+        }
 
-            for (function_index, export_name) in &self.exported_functions {
-                writeln!(f, " {:#5} '{}'", function_index, export_name)?;
-            }
+        // a function with no decision points has a single path through it
+        let mut complexity = 1;
+        let mut reachability = Reachability::default();
+        let mut reader = function_body.get_operators_reader()?;
+        while !reader.eof() {
+            let operator = reader.read()?;
+            let opname = operator_name(&operator).to_string();
 
-            let mut called_functions = vec!();
-            for called_list in self.static_function_calls.values() {
-                called_functions.extend(called_list);
+            self.detect_features(&operator, &opname);
+
+            // like the features used, growth of memory is found whatever the analysis
+            match operator {
+                Operator::MemoryGrow { .. } => {
+                    self.uses_memory_grow = true;
+                    *self.memory_grow_sites.entry(*index).or_insert(0) += 1;
+                },
+                Operator::MemorySize { .. } => self.memory_size_count += 1,
+                _ => {},
             }
-            called_functions.sort();
-            called_functions.dedup();
-            if !called_functions.is_empty() {
-                writeln!(f, "\nStatically Called ({}): {}",
-                         called_functions.len(), RangeVec::from(&called_functions))?;
+
+            if !self.include_functions {
+                continue;
             }
 
-            if !self.dynamic_dispatch_functions.is_empty() {
-                let mut dynamic = self.dynamic_dispatch_functions.clone();
-                dynamic.sort();
-                writeln!(f, "\nDynamic Dispatch ({}): {}",
-                         dynamic.len(), RangeVec::from(&dynamic))?;
+            match operator {
+                Operator::Call { function_index } | Operator::ReturnCall { function_index } =>
+                    self.add_function_call(*index, function_index as usize),
+                Operator::CallIndirect { type_index, table_index, .. } =>
+                    self.call_indirect_sites.push((*index, type_index, table_index)),
+                Operator::If { .. } | Operator::BrIf { .. } | Operator::Loop { .. } => complexity += 1,
+                Operator::BrTable { ref targets } => {
+                    complexity += targets.len();
+                    self.br_table_targets.push(targets.len() as usize);
+                },
+                _ => {},
             }
 
-            let mut all_functions: Vec<usize> = (0..self.implemented_function_count)
-                .map(|e| e as usize ).collect();
-            // Remove all functions that have been called by others
-            all_functions.retain(|e| {
-                !called_functions.contains(e)
-            });
-            // Remove all imported functions
-            all_functions.retain(|e| {
-                !self.imported_functions.contains_key(e)
-            });
-            // Remove all exported functions
-            all_functions.retain(|e| {
-                !self.exported_functions.contains_key(e)
-            });
-            // Remove functions that maybe called dynamically at runtime via a table
-            all_functions.retain(|e| {
-                !self.dynamic_dispatch_functions.contains(e)
-            });
-            if !all_functions.is_empty() {
-                all_functions.sort();
-                writeln!(f, "\nUncalled ({}): {}", all_functions.len(),
-                         RangeVec::from(&all_functions))?;
+            if reachability.is_dead(&operator) {
+                self.dead_instruction_count += 1;
             }
 
-            if self.include_function_call_tree {
-                writeln!(f, "\nCall Tree:")?;
-                for index in self.static_function_calls.keys() {
-                    if let Some(name) = self.exported_functions.get(index) {
-                        self.print_call_tree(index, name, f)?;
-                    }
-                }
+            if CONTROL_FLOW_OPERATORS.contains(&opname.as_str()) {
+                *self.control_flow_stats.entry(opname.clone()).or_insert(0) += 1;
             }
 
             if self.include_operators {
-                writeln!(f, "\nOperators:")?;
-                writeln!(f, "Operators Count: {}", self.operator_count)?;
-                writeln!(f, "Operator Usage:")?;
-                writeln!(f, "\tOperator             Count")?;
-                for (opname, count) in &self.sorted_operator_usage {
-                    writeln!(f, "\t{:#018}{:#8}", opname, count)?;
-                }
+                self.function_operator_usage.entry(*index).or_default().entry(opname.clone())
+                    .and_modify(|count| *count += 1)
+                    .or_insert(1);
+                self.operator_usage.entry(opname)
+                    .and_modify(|count| *count += 1)
+                    .or_insert(1);
+                self.operator_count += 1;
             }
         }
 
+        if self.include_functions {
+            self.function_complexity.insert(*index, complexity);
+            self.implemented_function_count += 1;
+        }
+
+        *index += 1;
+
         Ok(())
     }
-}
 
-/// Analyze the parsed [Module] to see what sections it has and operators it uses
-pub fn analyze(module: &Module,
-               include_sections: bool,
-               include_functions: bool,
+    // Analyze function bodies, the first of which has index `first_index`, in parallel on `jobs`
+    // threads. Each thread analyzes a run of consecutive functions into a partial analysis, and
+    // the partials are merged in function order so the result is the same as analyzing them
+    // one after the other
+    fn add_functions_in_parallel(&mut self, bodies: &[&FunctionBody], first_index: usize,
+                                 jobs: usize) -> Result<()> {
+        let pool = rayon::ThreadPoolBuilder::new().num_threads(jobs).build()
+            .chain_err(|| "Could not start the threads to analyze functions")?;
+        let chunk_size = bodies.len().div_ceil(jobs).max(1);
+        let partials: Vec<Result<Analysis>> = pool.install(|| {
+            bodies.par_chunks(chunk_size).enumerate().map(|(chunk_index, chunk)| {
+                let mut partial = Analysis {
+                    include_functions: self.include_functions,
+                    include_operators: self.include_operators,
+                    include_function_call_tree: self.include_function_call_tree,
+                    types: self.types.clone(),
+                    ..Default::default() };
+                let mut index = first_index + chunk_index * chunk_size;
+                for body in chunk {
+                    partial.add_function(body, &mut index)?;
+                }
+                Ok(partial)
+            }).collect()
+        });
+
+        for partial in partials {
+            self.merge_functions(partial?);
+        }
+
+        Ok(())
+    }
+
+    // Merge in the results of analyzing function bodies from a partial analysis of them
+    fn merge_functions(&mut self, partial: Analysis) {
+        fn add_counts(counts: &mut BTreeMap<String, u64>, other: BTreeMap<String, u64>) {
+            for (name, count) in other {
+                *counts.entry(name).or_insert(0) += count;
+            }
+        }
+
+        self.implemented_function_count += partial.implemented_function_count;
+        self.function_sizes.extend(partial.function_sizes);
+        self.function_locals.extend(partial.function_locals);
+        self.function_complexity.extend(partial.function_complexity);
+        self.br_table_targets.extend(partial.br_table_targets);
+        add_counts(&mut self.control_flow_stats, partial.control_flow_stats);
+        self.dead_instruction_count += partial.dead_instruction_count;
+        self.call_indirect_sites.extend(partial.call_indirect_sites);
+        self.static_function_calls.extend(partial.static_function_calls);
+        self.function_operator_usage.extend(partial.function_operator_usage);
+        add_counts(&mut self.operator_usage, partial.operator_usage);
+        self.operator_count += partial.operator_count;
+
+        self.uses_simd |= partial.uses_simd;
+        self.simd_operator_count += partial.simd_operator_count;
+        self.uses_relaxed_simd |= partial.uses_relaxed_simd;
+        self.relaxed_simd_operator_count += partial.relaxed_simd_operator_count;
+        self.uses_bulk_memory |= partial.uses_bulk_memory;
+        add_counts(&mut self.bulk_memory_usage, partial.bulk_memory_usage);
+        self.uses_threads |= partial.uses_threads;
+        self.atomic_operators.extend(partial.atomic_operators);
+        self.memory_init_segments.extend(partial.memory_init_segments);
+        self.uses_multi_value |= partial.uses_multi_value;
+        self.uses_reference_types |= partial.uses_reference_types;
+        self.reference_type_operator_count += partial.reference_type_operator_count;
+        self.uses_memory_grow |= partial.uses_memory_grow;
+        self.memory_grow_sites.extend(partial.memory_grow_sites);
+        self.memory_size_count += partial.memory_size_count;
+        self.uses_sign_extension |= partial.uses_sign_extension;
+        self.sign_extension_operator_count += partial.sign_extension_operator_count;
+        self.uses_nontrapping_float_to_int |= partial.uses_nontrapping_float_to_int;
+        self.nontrapping_float_to_int_count += partial.nontrapping_float_to_int_count;
+        self.uses_floating_point |= partial.uses_floating_point;
+        self.floating_point_operator_count += partial.floating_point_operator_count;
+        self.uses_gc |= partial.uses_gc;
+        self.gc_features.extend(partial.gc_features);
+        self.uses_tail_calls |= partial.uses_tail_calls;
+        self.tail_call_count += partial.tail_call_count;
+        self.uses_exceptions |= partial.uses_exceptions;
+        self.exception_operator_count += partial.exception_operator_count;
+    }
+
+    fn add_functions(&mut self, module: &Module, reader: &FunctionSectionReader) -> Result<()> {
+        self.add_section("FunctionSection", Some(reader.count()), &reader.range())?;
+
+        for type_index in module.section_items(reader)? {
+            self.function_types.push(type_index?);
+        }
+
+        Ok(())
+    }
+
+    /// The signature of the function at `function_index`, from the type it was declared with
+    pub fn function_signature(&self, function_index: usize) -> Option<String> {
+        let type_index = self.function_types.get(function_index)?;
+        match self.types.get(*type_index as usize)? {
+            Type::Func(func_type) => Some(signature(func_type)),
+            _ => None,
+        }
+    }
+
+    fn add_exports(&mut self, module: &Module, reader: &ExportSectionReader) -> Result<()> {
+        self.add_section("ExportSection", Some(reader.count()), &reader.range())?;
+        self.export_count += reader.count();
+
+        for export in module.section_items(reader)?.flatten() {
+            if export.kind == ExternalKind::Func && matches!(export.name, "_start" | "main") {
+                self.add_entry_point(export.index as usize, export.name);
+            }
+            let exports = match export.kind {
+                ExternalKind::Func => {
+                    if self.include_functions {
+                        self.exported_functions.insert(export.index as usize,
+                                                       export.name.to_owned());
+                    }
+                    continue;
+                }
+                ExternalKind::Memory => &mut self.exported_memories,
+                ExternalKind::Global => &mut self.exported_globals,
+                ExternalKind::Table => &mut self.exported_tables,
+                _ => continue,
+            };
+            // the same item can be exported under more than one name
+            exports.entry(export.index as usize).or_default().push(export.name.to_owned());
+        }
+
+        Ok(())
+    }
+
+    // Record an exported function with the name of a conventional entry point, preferring the
+    // WASI `_start` to `main` when both are exported
+    fn add_entry_point(&mut self, index: usize, name: &str) {
+        let entry_point = (index, name.to_owned());
+        if name == "_start" {
+            self.other_entry_point = self.entry_point.replace(entry_point);
+        } else if self.entry_point.is_none() {
+            self.entry_point = Some(entry_point);
+        } else {
+            self.other_entry_point = Some(entry_point);
+        }
+    }
+
+    // Record the WASI ABI named by an import namespace, keeping the first one found
+    fn detect_wasi(&mut self, namespace: &str) {
+        if self.wasi.is_some() {
+            return;
+        }
+        self.wasi = match namespace {
+            "wasi_snapshot_preview1" => Some("snapshot_preview1".into()),
+            "wasi_unstable" => Some("unstable".into()),
+            _ if namespace.starts_with("wasi:") => Some("preview2 (component model)".into()),
+            _ => None,
+        };
+    }
+
+    fn add_imports(&mut self, module: &Module, reader: &ImportSectionReader,
+                   function_index: &mut usize) -> Result<()> {
+        self.add_section("ImportSection", Some(reader.count()), &reader.range())?;
+
+        // imports come before any definitions in each index space, so they are numbered from 0
+        for import in module.section_items(reader)?.flatten() {
+            self.detect_wasi(import.module);
+            let group = self.import_groups.entry(import.module.to_owned()).or_default();
+            for counts in [group, &mut self.import_totals] {
+                match import.ty {
+                    TypeRef::Func(_) => counts.functions += 1,
+                    TypeRef::Memory(_) => counts.memories += 1,
+                    TypeRef::Global(_) => counts.globals += 1,
+                    TypeRef::Table(_) => counts.tables += 1,
+                    TypeRef::Tag(_) => counts.tags += 1,
+                }
+            }
+
+            match import.ty {
+                TypeRef::Func(type_index) => {
+                    if self.include_functions {
+                        self.imported_functions.insert(*function_index, import.name.to_owned());
+                    }
+                    self.function_types.push(type_index);
+                    *function_index += 1;
+                },
+                TypeRef::Table(table_type) => {
+                    self.imported_tables.insert(self.imported_tables.len(), import.name.to_owned());
+                    self.add_table(&table_type, true)
+                },
+                TypeRef::Memory(memory_type) => {
+                    self.imported_memories.insert(self.imported_memories.len(),
+                                                  import.name.to_owned());
+                    self.add_memory(&memory_type)
+                },
+                TypeRef::Tag(_) => self.add_tags(1),
+                TypeRef::Global(global_type) => {
+                    self.imported_globals.insert(self.imported_globals.len(),
+                                                 import.name.to_owned());
+                    self.globals.push(GlobalInfo::new(&global_type, None));
+                },
+            }
+        }
+
+        Ok(())
+    }
+
+    // Print a list of imported or exported externals, if there are any
+    fn print_externals(title: &str, externals: &BTreeMap<usize, String>, f: &mut fmt::Formatter)
+        -> fmt::Result {
+        if !externals.is_empty() {
+            writeln!(f, "\n{} ({}):", title, externals.len())?;
+            for (index, name) in externals {
+                writeln!(f, " {:#5} '{}'", index, name)?;
+            }
+        }
+        Ok(())
+    }
+
+    // Print exported memories, globals or tables with all the names each one is exported as
+    fn print_exported_externals(title: &str, externals: &BTreeMap<usize, Vec<String>>,
+                                f: &mut fmt::Formatter) -> fmt::Result {
+        if !externals.is_empty() {
+            writeln!(f, "\n{} ({}):", title, externals.len())?;
+            for (index, names) in externals {
+                let names: Vec<String> = names.iter().map(|name| format!("'{}'", name)).collect();
+                writeln!(f, " {:#5} {}", index, names.join(", "))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Produce the section analysis as CSV, with a header row and one row per section
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("section_type,header_start,content_start,content_end,size,item_count\n");
+        for section in &self.sections {
+            let item_count = section.item_count.map(|count| count.to_string()).unwrap_or_default();
+            csv.push_str(&format!("{},{},{},{},{},{}\n",
+                                  section.section_type,
+                                  section.header_location,
+                                  section.range.start,
+                                  section.range.end - 1,
+                                  section.size,
+                                  item_count));
+        }
+        csv
+    }
+
+    /// Produce the section analysis and the summary of the functions, when they were analyzed,
+    /// as GitHub flavored Markdown tables with the numeric columns right-aligned
+    pub fn to_markdown(&self) -> String {
+        let mut markdown = String::new();
+        if self.include_sections {
+            markdown.push_str("## Sections\n\n");
+            markdown.push_str("| Type | Header Start | Content Start | Content End | Size | % | Items |\n");
+            markdown.push_str("|------|---:|---:|---:|---:|---:|---:|\n");
+            for section in &self.sections {
+                let item_count = section.item_count.map(|count| count.to_string()).unwrap_or_default();
+                markdown.push_str(&format!("| {} | {:#x} | {:#x} | {:#x} | {} | {:.1} | {} |\n",
+                                           section.section_type,
+                                           section.header_location,
+                                           section.range.start,
+                                           section.range.end - 1,
+                                           section.size,
+                                           section.percentage,
+                                           item_count));
+            }
+            markdown.push_str(&format!("\nTotal Size: {}\n", self.sections_size_total));
+        }
+
+        if self.include_functions {
+            if !markdown.is_empty() {
+                markdown.push('\n');
+            }
+            markdown.push_str("## Functions\n\n");
+            markdown.push_str(&format!("Imported: {}, Implemented: {}, Exported: {}\n\n",
+                                       self.imported_functions.len(),
+                                       self.implemented_function_count,
+                                       self.exported_functions.len()));
+            let locals: HashMap<usize, u32> = self.function_locals.iter().copied().collect();
+            markdown.push_str("| Function | Name | Size | Locals | Complexity |\n");
+            markdown.push_str("|---:|------|---:|---:|---:|\n");
+            for (index, size) in self.function_sizes.iter().take(self.top_limit()) {
+                let name = self.function_names.get(index)
+                    .or_else(|| self.exported_functions.get(index))
+                    .map(|name| name.replace('|', "\\|"))
+                    .unwrap_or_default();
+                markdown.push_str(&format!("| {} | {} | {} | {} | {} |\n", index, name, size,
+                                           locals.get(index).copied().unwrap_or_default(),
+                                           self.function_complexity.get(index).copied()
+                                               .unwrap_or_default()));
+            }
+        }
+        markdown
+    }
+
+    /// Count the functions in each of the function size buckets, in order of increasing size,
+    /// as a Vec of (bucket label, count of functions)
+    pub fn size_histogram(&self) -> Vec<(String, usize)> {
+        let mut histogram = vec!();
+        let mut lower = 0;
+        for upper in SIZE_HISTOGRAM_BUCKETS {
+            let count = self.function_sizes.iter()
+                .filter(|(_, size)| *size >= lower && *size <= upper).count();
+            histogram.push((format!("{}-{}", lower, upper), count));
+            lower = upper + 1;
+        }
+        let count = self.function_sizes.iter().filter(|(_, size)| *size >= lower).count();
+        histogram.push((format!("{}+", lower), count));
+        histogram
+    }
+
+    /// Restrict the sections to those whose type is one of `section_types`, which must all be
+    /// names from [SECTION_TYPES]. The total size of all sections is unchanged
+    pub fn filter_sections(&mut self, section_types: &[String]) -> Result<()> {
+        for section_type in section_types {
+            if !SECTION_TYPES.contains(&section_type.as_str()) {
+                return Err(Error::UnknownSectionType(section_type.clone()));
+            }
+        }
+        self.sections.retain(|section| section_types.contains(&section.section_type));
+        self.sections_filtered = true;
+        Ok(())
+    }
+
+    /// A condensed report of the analysis on a few lines, that needs none of the sections,
+    /// functions, operators or call tree to have been included in the analysis
+    pub fn summary(&self) -> String {
+        let imported_functions = self.import_totals.functions;
+        let import_count = imported_functions + self.import_totals.memories +
+            self.import_totals.globals + self.import_totals.tables + self.import_totals.tags;
+        let mut summary = format!("Size: {} bytes, version {}, {}\n", self.file_size, self.version,
+                                  self.module_kind);
+        summary.push_str(&format!("Functions: {} ({} imported, {} implemented)\n",
+                                  self.function_types.len(), imported_functions,
+                                  self.function_types.len().saturating_sub(imported_functions)));
+        summary.push_str(&format!("Imports: {}, Exports: {}\n", import_count, self.export_count));
+        if let Some((index, name)) = &self.entry_point {
+            summary.push_str(&format!("Entry Point: '{}' (function #{})\n", name, index));
+        }
+        if let Some((section_type, size)) = &self.largest_section {
+            let percentage = *size as f64 * 100.0 / self.file_size.max(1) as f64;
+            summary.push_str(&format!("Largest Section: {} ({} bytes, {:.1}%)\n", section_type,
+                                      size, percentage));
+        }
+        summary
+    }
+
+    /// Reorder the sections as specified by `sort`, with sections that tie ordered by their
+    /// offset in the file
+    pub fn sort_sections(&mut self, sort: SectionSort) {
+        match sort {
+            SectionSort::Offset => self.sections.sort_by_key(|section| section.header_location),
+            SectionSort::Size => self.sections.sort_by(|a, b| b.size.cmp(&a.size)
+                .then(a.header_location.cmp(&b.header_location))),
+            SectionSort::Type => self.sections.sort_by(|a, b| a.section_type.cmp(&b.section_type)
+                .then(a.header_location.cmp(&b.header_location))),
+        }
+    }
+
+    /// The functions whose body is larger than `max_size` bytes, largest first, as a Vec of
+    /// (function index, name from the name section or exports, size of body in bytes)
+    pub fn oversized_functions(&self, max_size: usize) -> Vec<(usize, String, usize)> {
+        self.function_sizes.iter()
+            .filter(|(_, size)| *size > max_size)
+            .map(|(index, size)| {
+                let name = self.function_names.get(index)
+                    .or_else(|| self.exported_functions.get(index))
+                    .cloned()
+                    .unwrap_or_default();
+                (*index, name, *size)
+            })
+            .collect()
+    }
+
+    /// Record the SHA-256 hash of `buf`, the bytes of the whole file that was analyzed,
+    /// including any custom sections
+    pub fn hash(&mut self, buf: &[u8]) {
+        self.module_hash = Sha256::digest(buf).iter().map(|byte| format!("{:02x}", byte)).collect();
+    }
+
+    /// The indices of the implemented functions, which in the wasm function index space follow
+    /// those of all the imported functions
+    pub fn implemented_functions(&self) -> Range<usize> {
+        let first_implemented = self.imported_functions.len();
+        first_implemented..first_implemented + self.implemented_function_count as usize
+    }
+
+    /// Produce the static call graph in Graphviz DOT format
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph calls {\n");
+        for index in self.implemented_functions() {
+            let label = self.function_label(&index).replace('"', "\\\"");
+            let mut attributes = vec!(format!("label=\"{}\"", label));
+            if self.exported_functions.contains_key(&index) {
+                attributes.push("shape=box".into());
+            }
+            if self.dynamic_dispatch_functions.contains(&index) {
+                attributes.push("style=filled".into());
+                attributes.push("fillcolor=lightblue".into());
+            }
+            dot.push_str(&format!("    {} [{}];\n", index, attributes.join(", ")));
+        }
+
+        let mut callers: Vec<&usize> = self.static_function_calls.keys().collect();
+        callers.sort();
+        for caller in callers {
+            for called in &self.static_function_calls[caller] {
+                dot.push_str(&format!("    {} -> {};\n", caller, called));
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Demangle the Rust and C++ symbols used as the names of functions, leaving any names
+    /// that are not mangled unchanged
+    pub fn demangle_names(&mut self) {
+        for name in self.function_names.values_mut().chain(self.imported_functions.values_mut()) {
+            *name = demangle(name);
+        }
+    }
+
+    /// Find the index of a function from either its index or its exported or debug name
+    pub fn resolve_function(&self, name_or_index: &str) -> Result<usize> {
+        let function_count = self.imported_functions.len() + self.implemented_function_count as usize;
+        let index = match name_or_index.parse::<usize>() {
+            Ok(index) if index < function_count => Some(index),
+            Ok(_) => None,
+            Err(_) => self.exported_functions.iter()
+                .chain(self.function_names.iter())
+                .find(|(_, name)| name.as_str() == name_or_index)
+                .map(|(index, _)| *index),
+        };
+
+        match index {
+            Some(index) => Ok(index),
+            None => Err(Error::FunctionNotFound { function: name_or_index.into(),
+                                                  function_count }),
+        }
+    }
+
+    /// Restrict the per-function reports (operators, sizes, complexity and call tree) to just
+    /// the function identified by `name_or_index`
+    pub fn select_function(&mut self, name_or_index: &str) -> Result<usize> {
+        let index = self.resolve_function(name_or_index)?;
+        self.selected_function = Some(index);
+        self.function_sizes.retain(|(function_index, _)| *function_index == index);
+        self.function_locals.retain(|(function_index, _)| *function_index == index);
+        self.function_complexity.retain(|function_index, _| *function_index == index);
+        self.operator_usage = self.function_operator_usage.remove(&index).unwrap_or_default();
+        self.operator_count = self.operator_usage.values().sum();
+        self.sort_operator_usage();
+        Ok(index)
+    }
+
+    fn sort_operator_usage(&mut self) {
+        let mut vec: Vec<(String, u64)> = self.operator_usage.iter()
+            .map(|(s, c)| (s.to_string(), *c)).collect();
+        vec.sort_by_key(|b| std::cmp::Reverse(b.1));
+        self.sorted_operator_usage = vec;
+    }
+
+    // Warn about each known section that appears before, or is a repeat of, an earlier one
+    fn check_section_order(&mut self, sections: &[(u8, Range<usize>)]) {
+        let mut previous: Option<(usize, u8)> = None; // (position in SECTION_ORDER, id)
+        for (id, range) in sections {
+            let position = match SECTION_ORDER.iter().position(|known| known == id) {
+                Some(position) => position,
+                None => continue,
+            };
+            match previous {
+                Some((previous_position, previous_id)) if position <= previous_position =>
+                    self.section_order_warnings.push(format!(
+                        "{} at offset {:#x} is out of order after {}",
+                        section_name(*id), range.start, section_name(previous_id))),
+                _ => previous = Some((position, *id)),
+            }
+        }
+    }
+
+    fn post_process(&mut self) {
+        // size the sections relative to the total, now it is known
+        if self.sections_size_total > 0 {
+            for section in &mut self.sections {
+                section.percentage = section.size as f64 * 100.0 / self.sections_size_total as f64;
+            }
+        }
+
+        // order the operator usage
+        self.sort_operator_usage();
+
+        // order the functions by descending size
+        self.function_sizes.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+
+        // order the functions by descending number of locals
+        self.function_locals.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+
+        let mut called_functions = vec!();
+        for called_list in self.static_function_calls.values() {
+            called_functions.extend(called_list);
+        }
+        called_functions.sort();
+        called_functions.dedup();
+        self.statically_called_functions = called_functions;
+
+        // Find implemented functions not reachable from the exports, start function or a table
+        let reachable = self.reachable_functions([]);
+        self.uncalled_functions = self.implemented_functions()
+            .filter(|index| !reachable.contains(index))
+            .collect();
+
+        // invert the calls made to imported functions
+        for (caller, called_list) in &self.static_function_calls {
+            for called in called_list {
+                if self.imported_functions.contains_key(called) {
+                    self.import_callers.entry(*called).or_default().push(*caller);
+                }
+            }
+        }
+        for callers in self.import_callers.values_mut() {
+            callers.sort();
+        }
+
+        // find imported functions that are never called, referenced from a table, or re-exported
+        self.unused_imports = self.imported_functions.keys()
+            .filter(|index| !self.static_function_calls.values().any(|called| called.contains(index)))
+            .filter(|index| !self.dynamic_dispatch_functions.contains(index))
+            .filter(|index| !self.exported_functions.contains_key(index))
+            .copied()
+            .collect();
+
+        if self.include_function_call_tree {
+            let roots: Vec<usize> = self.exported_functions.keys().copied().collect();
+            let components = self.call_graph_components(&roots);
+            let mut component_of = HashMap::new();
+            for (component, members) in components.iter().enumerate() {
+                for member in members {
+                    component_of.insert(*member, component);
+                }
+            }
+
+            // components only call components found before them, so the depth of each can be
+            // found from those already known, counting each cycle of calls as one level
+            let mut depths = vec!(0; components.len());
+            let mut recursive = BTreeSet::new();
+            for (component, members) in components.iter().enumerate() {
+                let mut depth = 0;
+                for member in members {
+                    for called in self.called_functions(*member) {
+                        let called_component = component_of[called];
+                        if called_component != component {
+                            depth = depth.max(1 + depths[called_component]);
+                        }
+                    }
+                }
+                depths[component] = depth;
+                if members.len() > 1 || self.called_functions(members[0]).contains(&members[0]) {
+                    recursive.extend(members);
+                }
+            }
+            self.max_call_depth = depths.into_iter().max().unwrap_or(0);
+            self.recursive_functions = recursive.into_iter().collect();
+        }
+    }
+
+    // The indexes of the functions called statically from the function at `index`
+    fn called_functions(&self, index: usize) -> &[usize] {
+        self.static_function_calls.get(&index).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    // Find the strongly connected components of the static call graph reachable from `roots`,
+    // using Tarjan's algorithm without recursion so a deep call graph can't overflow the stack.
+    // Return the functions in each component, with a component never calling one after it
+    fn call_graph_components(&self, roots: &[usize]) -> Vec<Vec<usize>> {
+        let mut visited: HashMap<usize, (usize, usize)> = HashMap::new(); // (order, lowlink)
+        let mut stack = vec!();
+        let mut on_stack = HashSet::new();
+        let mut components = vec!();
+
+        for root in roots {
+            if visited.contains_key(root) {
+                continue;
+            }
+            // (function, position in its list of called functions of the next one to visit)
+            let mut work = vec!((*root, 0));
+            while let Some(&(function, next)) = work.last() {
+                if next == 0 && !visited.contains_key(&function) {
+                    visited.insert(function, (visited.len(), visited.len()));
+                    stack.push(function);
+                    on_stack.insert(function);
+                }
+
+                if let Some(called) = self.called_functions(function).get(next) {
+                    if let Some(top) = work.last_mut() {
+                        top.1 += 1;
+                    }
+                    match visited.get(called) {
+                        None => work.push((*called, 0)),
+                        Some(&(order, _)) if on_stack.contains(called) => {
+                            if let Some(entry) = visited.get_mut(&function) {
+                                entry.1 = entry.1.min(order);
+                            }
+                        }
+                        Some(_) => {}
+                    }
+                    continue;
+                }
+
+                work.pop();
+                let (order, lowlink) = visited[&function];
+                if let Some(&(caller, _)) = work.last() {
+                    if let Some(entry) = visited.get_mut(&caller) {
+                        entry.1 = entry.1.min(lowlink);
+                    }
+                }
+                if order == lowlink {
+                    let mut component = vec!();
+                    while let Some(member) = stack.pop() {
+                        on_stack.remove(&member);
+                        component.push(member);
+                        if member == function {
+                            break;
+                        }
+                    }
+                    component.sort();
+                    components.push(component);
+                }
+            }
+        }
+
+        components
+    }
+
+    // The number of entries to show in per-function reports
+    fn top_limit(&self) -> usize {
+        self.top.unwrap_or(usize::MAX)
+    }
+
+    // Format a function index, appending the function's name if one is known
+    fn function_label(&self, index: &usize) -> String {
+        match self.function_names.get(index) {
+            Some(name) => format!("#{} {}", index, name),
+            None => format!("#{}", index),
+        }
+    }
+
+    fn print_function_names(&self, indexes: &[usize], f: &mut fmt::Formatter) -> fmt::Result {
+        for index in indexes {
+            if let Some(name) = self.function_names.get(index) {
+                writeln!(f, " {:#5} '{}'", index, name)?;
+            }
+        }
+        Ok(())
+    }
+
+    // Find all functions that can be reached by following static calls starting from the functions
+    // that can be called from outside the module: exports, the start function and table elements,
+    // plus any `extra_roots` the caller knows can be reached some other way
+    pub(crate) fn reachable_functions(&self, extra_roots: impl IntoIterator<Item = usize>)
+        -> BTreeSet<usize> {
+        let mut reachable = BTreeSet::new();
+        let mut to_visit: Vec<usize> = self.exported_functions.keys().copied()
+            .chain(self.start_function)
+            .chain(self.dynamic_dispatch_functions.iter().copied())
+            .chain(extra_roots)
+            .collect();
+
+        while let Some(index) = to_visit.pop() {
+            if reachable.insert(index) {
+                if let Some(called_list) = self.static_function_calls.get(&index) {
+                    to_visit.extend(called_list.iter().filter(|called| !reachable.contains(called)));
+                }
+            }
+        }
+
+        reachable
+    }
+
+    fn print_called_list(&self, call_chain: Vec<usize>, f: &mut fmt::Formatter) -> fmt::Result {
+        let index = call_chain.last().unwrap_or(&1);
+        if let Some(called_list) = self.static_function_calls.get(index) {
+            let level = call_chain.len();
+            for called in called_list {
+                if call_chain.contains(called) {
+                    writeln!(f, "     {}+- {} Cyclic call", format_args!("{: >1$}", "", level * 3),
+                             self.function_label(called))?;
+                } else {
+                    writeln!(f, "     {}+- {}", format_args!("{: >1$}", "", level * 3),
+                             self.function_label(called))?;
+                    let mut new_chain = call_chain.clone();
+                    new_chain.push(*called);
+                    self.print_called_list(new_chain, f)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Find the indexes of all functions that statically call the function at `index`
+    pub fn callers_of(&self, index: usize) -> Vec<usize> {
+        let mut callers: Vec<usize> = self.static_function_calls.iter()
+            .filter(|(_, called_list)| called_list.contains(&index))
+            .map(|(caller, _)| *caller)
+            .collect();
+        callers.sort();
+        callers
+    }
+
+    fn print_caller_list(&self, call_chain: Vec<usize>, f: &mut fmt::Formatter) -> fmt::Result {
+        let index = call_chain.last().unwrap_or(&1);
+        let level = call_chain.len();
+        for caller in self.callers_of(*index) {
+            if call_chain.contains(&caller) {
+                writeln!(f, "     {}+- {} Cyclic call", format_args!("{: >1$}", "", level * 3),
+                         self.function_label(&caller))?;
+            } else {
+                writeln!(f, "     {}+- {}", format_args!("{: >1$}", "", level * 3),
+                         self.function_label(&caller))?;
+                let mut new_chain = call_chain.clone();
+                new_chain.push(caller);
+                self.print_caller_list(new_chain, f)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Print the chain of callers upwards from the function at `index`
+    pub fn print_callers(&self, index: usize, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Callers of {}:", self.function_label(&index))?;
+        if self.callers_of(index).is_empty() {
+            writeln!(f, "\t{} has no callers", self.function_label(&index))
+        } else {
+            writeln!(f, "\t{}", self.function_label(&index))?;
+            self.print_caller_list(vec!(index), f)
+        }
+    }
+
+    fn print_call_tree(&self, root_index: &usize, name: &str, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "\t{} '{}'", self.function_label(root_index), name)?;
+        self.print_called_list(vec!(*root_index), f)?;
+        writeln!(f)
+    }
+}
+
+/// Displays the chain of callers of a function in an [Analysis]
+pub struct Callers<'a> {
+    pub analysis: &'a Analysis,
+    pub index: usize,
+}
+
+impl<'a> fmt::Display for Callers<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.analysis.print_callers(self.index, f)
+    }
+}
+
+/// Displays a comparison of the sizes of each type of section in two [Analysis] of modules,
+/// each of which must have been analyzed with sections included
+pub struct SectionSizeDiff<'a> {
+    pub before: &'a Analysis,
+    pub after: &'a Analysis,
+}
+
+impl<'a> SectionSizeDiff<'a> {
+    /// The total size, including headers, of each type of section in `before` and `after`,
+    /// in the order they first appear. A type present in only one of them has a size of 0
+    /// in the other
+    pub fn sizes(&self) -> Vec<(String, usize, usize)> {
+        let mut sizes: Vec<(String, usize, usize)> = vec![];
+        for (analysis, after) in [(self.before, false), (self.after, true)] {
+            for section in &analysis.sections {
+                let size = section.range.end - section.header_location;
+                let existing = sizes.iter()
+                    .position(|(section_type, _, _)| *section_type == section.section_type);
+                let position = match existing {
+                    Some(position) => position,
+                    None => {
+                        sizes.push((section.section_type.clone(), 0, 0));
+                        sizes.len() - 1
+                    },
+                };
+                if after {
+                    sizes[position].2 += size;
+                } else {
+                    sizes[position].1 += size;
+                }
+            }
+        }
+        sizes
+    }
+}
+
+impl<'a> fmt::Display for SectionSizeDiff<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Section                     Before     After     Delta")?;
+        for (section_type, before, after) in self.sizes() {
+            writeln!(f, "{:<24}{:10}{:10}{:+10}", section_type, before, after,
+                     after as i64 - before as i64)?;
+        }
+        let (before, after) = (self.before.sections_size_total, self.after.sections_size_total);
+        writeln!(f, "{:<24}{:10}{:10}{:+10}", "Total", before, after, after as i64 - before as i64)
+    }
+}
+
+#[derive(PartialEq, Debug, Default)]
+struct RangeVec(Vec<RangeVecEntry>);
+#[derive(PartialEq, Debug)]
+enum RangeVecEntry {
+    RangeEntry(RangeInclusive<usize>),
+    SingleEntry(usize)
+}
+
+impl fmt::Display for RangeVec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "[")?;
+        for (i, entry) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            match entry {
+                RangeVecEntry::RangeEntry(range) =>write!(f, "{}..{}",
+                                                          range.start(), range.end())?,
+                RangeVecEntry::SingleEntry(number) => write!(f, "{}", number)?,
+            }
+        }
+        write!(f, "]")
+    }
+}
+
+// assumes the input vector is already ordered
+impl From<&Vec<usize>> for RangeVec {
+    fn from(input: &Vec<usize>) -> Self {
+        let mut output: RangeVec = RangeVec::default();
+        let (mut start, rest) = match input.split_first() {
+            Some((first, rest)) => (*first, rest),
+            None => return output,
+        };
+        let mut end = start;
+        for i in rest.iter() {
+            if *i != end + 1 {
+                if start == end {
+                    output.0.push(RangeVecEntry::SingleEntry(end));
+                } else {
+                    output.0.push(RangeVecEntry::RangeEntry(start..=end));
+                }
+                start = *i;
+                end = *i;
+            } else {
+                end = *i;
+            }
+        }
+
+        if start == end {
+            output.0.push(RangeVecEntry::SingleEntry(end));
+        } else {
+            output.0.push(RangeVecEntry::RangeEntry(start..=end));
+        }
+
+        output
+    }
+}
+
+impl fmt::Display for Analysis {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if !self.module_hash.is_empty() {
+            writeln!(f, "Module Hash (SHA-256): {}\n", self.module_hash)?;
+        }
+
+        if let Some((index, name)) = &self.entry_point {
+            write!(f, "Entry Point: '{}' (function #{})", name, index)?;
+            if let Some((other_index, other_name)) = &self.other_entry_point {
+                write!(f, ", '{}' (function #{}) is also exported", other_name, other_index)?;
+            }
+            writeln!(f, "\n")?;
+        }
+
+        if !self.section_order_warnings.is_empty() {
+            for warning in &self.section_order_warnings {
+                writeln!(f, "WARNING Section order: {}", warning)?;
+            }
+            writeln!(f)?;
+        }
+
+        if self.include_sections {
+            writeln!(f, "Sections:")?;
+            Section::header(f)?;
+            for section in &self.sections {
+                writeln!(f, "{}", section)?;
+            }
+
+            if self.sections_filtered {
+                let subtotal: usize = self.sections.iter()
+                    .map(|section| section.range.end - section.header_location).sum();
+                writeln!(f, "Subtotal Size (selected sections only): {}", subtotal)?;
+            } else {
+                writeln!(f, "Total Size: {}", self.sections_size_total)?;
+            }
+        }
+
+        if !self.producers.is_empty() {
+            writeln!(f, "\nProducers:")?;
+            for (field, values) in &self.producers {
+                let values: Vec<String> = values.iter()
+                    .map(|(name, version)| format!("{} {}", name, version).trim().to_string())
+                    .collect();
+                writeln!(f, " {}: {}", field, values.join(", "))?;
+            }
+        }
+
+        if self.has_debug_info {
+            writeln!(f, "\nDebug Info ({} bytes):", self.debug_info_size)?;
+            for (name, size) in &self.debug_sections {
+                writeln!(f, " {:<24}{:#10}", name, size)?;
+            }
+        }
+
+        if !self.tables.is_empty() {
+            writeln!(f, "\nTables ({}):", self.tables.len())?;
+            for (table_index, table) in self.tables.iter().enumerate() {
+                writeln!(f, " {:#5} {}", table_index, table)?;
+            }
+        }
+
+        if self.active_data_segments > 0 || !self.passive_data_segments.is_empty() {
+            let passive_size: usize = self.passive_data_segments.iter().map(|(_, size)| size).sum();
+            writeln!(f, "\nData Segments:")?;
+            writeln!(f, " Active: {} ({} bytes)", self.active_data_segments, self.active_data_size)?;
+            writeln!(f, " Passive: {} ({} bytes)", self.passive_data_segments.len(), passive_size)?;
+            for (index, size) in &self.passive_data_segments {
+                if !self.memory_init_segments.contains(index) {
+                    writeln!(f, " {:#5} passive segment of {} bytes is not used by memory.init",
+                             index, size)?;
+                }
+            }
+        }
+
+        if !self.globals.is_empty() {
+            writeln!(f, "\nGlobals ({}):", self.globals.len())?;
+            for (global_index, global) in self.globals.iter().enumerate() {
+                writeln!(f, " {:#5} {}", global_index, global)?;
+            }
+        }
+
+        if !self.import_groups.is_empty() {
+            writeln!(f, "\nImports by Module ({}):", self.import_groups.len())?;
+            for (namespace, group) in &self.import_groups {
+                writeln!(f, " {}: {}", namespace, group)?;
+            }
+        }
+
+        Self::print_externals("Imported Memories", &self.imported_memories, f)?;
+        Self::print_exported_externals("Exported Memories", &self.exported_memories, f)?;
+        Self::print_externals("Imported Globals", &self.imported_globals, f)?;
+        Self::print_exported_externals("Exported Globals", &self.exported_globals, f)?;
+        Self::print_externals("Imported Tables", &self.imported_tables, f)?;
+        Self::print_exported_externals("Exported Tables", &self.exported_tables, f)?;
+
+        if self.duplicate_type_count > 0 {
+            writeln!(f, "\nDuplicate Types ({}):", self.duplicate_type_count)?;
+            for (type_index, first_index) in &self.duplicate_types {
+                if let Some(Type::Func(func_type)) = self.types.get(*type_index) {
+                    writeln!(f, " {:#5} {} duplicates type {}", type_index, signature(func_type),
+                             first_index)?;
+                }
+            }
+        }
+
+        writeln!(f, "\nFeatures:")?;
+        if self.uses_simd {
+            writeln!(f, "SIMD: used ({} ops)", self.simd_operator_count)?;
+        } else {
+            writeln!(f, "SIMD: not used")?;
+        }
+        if self.uses_relaxed_simd {
+            writeln!(f, "Relaxed SIMD: used ({} ops)", self.relaxed_simd_operator_count)?;
+        } else {
+            writeln!(f, "Relaxed SIMD: not used")?;
+        }
+        if self.uses_bulk_memory {
+            writeln!(f, "Bulk Memory: used")?;
+            for (opname, count) in &self.bulk_memory_usage {
+                writeln!(f, "\t{:#018}{:#8}", opname, count)?;
+            }
+        } else {
+            writeln!(f, "Bulk Memory: not used")?;
+        }
+        if self.uses_threads {
+            writeln!(f, "WARNING Threads/Atomics: used")?;
+            for opname in &self.atomic_operators {
+                writeln!(f, "\t{}", opname)?;
+            }
+        } else {
+            writeln!(f, "Threads/Atomics: not used")?;
+        }
+        if self.uses_shared_memory {
+            writeln!(f, "WARNING Shared Memory: used (requires a SharedArrayBuffer host)")?;
+        } else {
+            writeln!(f, "Shared Memory: not used")?;
+        }
+        if self.uses_memory_grow {
+            let sites: u32 = self.memory_grow_sites.values().sum();
+            writeln!(f, "memory.grow: used ({} sites in {} functions, memory.size: {} ops)", sites,
+                     self.memory_grow_sites.len(), self.memory_size_count)?;
+        } else {
+            writeln!(f, "memory.grow: not used (memory.size: {} ops)", self.memory_size_count)?;
+        }
+        if self.uses_multi_value {
+            writeln!(f, "Multi-Value: used")?;
+        } else {
+            writeln!(f, "Multi-Value: not used")?;
+        }
+        if self.uses_reference_types {
+            writeln!(f, "Reference Types: used ({} ops)", self.reference_type_operator_count)?;
+        } else {
+            writeln!(f, "Reference Types: not used")?;
+        }
+        if self.uses_memory64 {
+            writeln!(f, "WARNING Memory64: used")?;
+        } else {
+            writeln!(f, "Memory64: not used")?;
+        }
+        if self.uses_tail_calls {
+            writeln!(f, "Tail Calls: used ({} ops)", self.tail_call_count)?;
+        } else {
+            writeln!(f, "Tail Calls: not used")?;
+        }
+        if self.uses_exceptions {
+            writeln!(f, "WARNING Exception Handling: used ({} tags, {} ops)", self.tag_count,
+                     self.exception_operator_count)?;
+        } else {
+            writeln!(f, "Exception Handling: not used")?;
+        }
+        if self.uses_sign_extension {
+            writeln!(f, "Sign Extension: used ({} ops)", self.sign_extension_operator_count)?;
+        } else {
+            writeln!(f, "Sign Extension: not used")?;
+        }
+        if self.uses_nontrapping_float_to_int {
+            writeln!(f, "Non-trapping Float-to-int: used ({} ops)",
+                     self.nontrapping_float_to_int_count)?;
+        } else {
+            writeln!(f, "Non-trapping Float-to-int: not used")?;
+        }
+        if self.uses_floating_point {
+            writeln!(f, "Floating Point: used ({} ops)", self.floating_point_operator_count)?;
+        } else {
+            writeln!(f, "Floating Point: not used")?;
+        }
+        if self.uses_gc {
+            let features: Vec<&str> = self.gc_features.iter().map(|s| s.as_str()).collect();
+            writeln!(f, "WARNING GC: used ({})", features.join(", "))?;
+        } else {
+            writeln!(f, "GC: not used")?;
+        }
+        writeln!(f, "WASI: {}", self.wasi.as_deref().unwrap_or("none"))?;
+
+        if self.include_functions {
+            writeln!(f, "\nFunctions:")?;
+            let totals = &self.import_totals;
+            writeln!(f, "Import Summary: {} function imports, {} memory imports, {} global imports, \
+                {} table imports", totals.functions, totals.memories, totals.globals, totals.tables)?;
+            writeln!(f, "Imported Functions ({}):", self.imported_functions.len())?;
+            for (function_index, import_name) in &self.imported_functions {
+                writeln!(f, " {:#5} '{}' {}", function_index, import_name,
+                         self.function_signature(*function_index).unwrap_or_default())?;
+            }
+            if !self.unused_imports.is_empty() {
+                writeln!(f, "Unused Imports ({}):", self.unused_imports.len())?;
+                for function_index in &self.unused_imports {
+                    if let Some(import_name) = self.imported_functions.get(function_index) {
+                        writeln!(f, " {:#5} '{}'", function_index, import_name)?;
+                    }
+                }
+            }
+            if !self.import_callers.is_empty() {
+                writeln!(f, "Import Callers:")?;
+                for (function_index, callers) in &self.import_callers {
+                    if let Some(import_name) = self.imported_functions.get(function_index) {
+                        writeln!(f, " {:#5} '{}' called by ({}): {}", function_index, import_name,
+                                 callers.len(), RangeVec::from(callers))?;
+                    }
+                }
+            }
+            writeln!(f, "Implemented ({}):", self.implemented_function_count)?;
+            writeln!(f, "Exported ({}):", self.exported_functions.len())?;
+
+            for (function_index, export_name) in &self.exported_functions {
+                writeln!(f, " {:#5} '{}' {}", function_index, export_name,
+                         self.function_signature(*function_index).unwrap_or_default())?;
+            }
+
+            if let Some(start_function) = &self.start_function {
+                writeln!(f, "Start Function: {}", self.function_label(start_function))?;
+            }
+
+            if !self.statically_called_functions.is_empty() {
+                writeln!(f, "\nStatically Called ({}): {}", self.statically_called_functions.len(),
+                         RangeVec::from(&self.statically_called_functions))?;
+                self.print_function_names(&self.statically_called_functions, f)?;
+            }
+
+            if !self.dynamic_dispatch_functions.is_empty() {
+                let mut dynamic = self.dynamic_dispatch_functions.clone();
+                dynamic.sort();
+                writeln!(f, "\nDynamic Dispatch ({}): {}",
+                         dynamic.len(), RangeVec::from(&dynamic))?;
+            }
+
+            if !self.call_indirect_sites.is_empty() {
+                writeln!(f, "\nIndirect Call Sites ({}):", self.call_indirect_sites.len())?;
+                let mut by_type: BTreeMap<u32, usize> = BTreeMap::new();
+                for (_, type_index, _) in &self.call_indirect_sites {
+                    *by_type.entry(*type_index).or_insert(0) += 1;
+                }
+                for (type_index, count) in by_type {
+                    let signature = match self.types.get(type_index as usize) {
+                        Some(Type::Func(func_type)) => signature(func_type),
+                        _ => String::new(),
+                    };
+                    writeln!(f, " type {:#5} {:<24}{:#8}", type_index, signature, count)?;
+                }
+            }
+
+            if !self.uncalled_functions.is_empty() {
+                writeln!(f, "\nUncalled ({}): {}", self.uncalled_functions.len(),
+                         RangeVec::from(&self.uncalled_functions))?;
+                self.print_function_names(&self.uncalled_functions, f)?;
+            }
+
+            if !self.function_sizes.is_empty() {
+                let total_size: usize = self.function_sizes.iter().map(|(_, size)| size).sum();
+                let mut cumulative = 0;
+                writeln!(f, "\nFunction Sizes:")?;
+                writeln!(f, "\tFunction      Size  Cumulative %")?;
+                for (function_index, size) in self.function_sizes.iter().take(self.top_limit()) {
+                    cumulative += size;
+                    writeln!(f, "\t{:<10}{:#8}{:#13.1}%", self.function_label(function_index), size,
+                             (cumulative * 100) as f64 / total_size as f64)?;
+                }
+            }
+
+            if !self.function_sizes.is_empty() {
+                let histogram = self.size_histogram();
+                let max_count = histogram.iter().map(|(_, count)| *count).max().unwrap_or(0).max(1);
+                writeln!(f, "\nFunction Size Histogram:")?;
+                for (bucket, count) in histogram {
+                    writeln!(f, "\t{:>8} {:#6} {}", bucket, count,
+                             "#".repeat(count * HISTOGRAM_BAR_WIDTH / max_count))?;
+                }
+            }
+
+            if !self.function_locals.is_empty() {
+                writeln!(f, "\nFunction Locals:")?;
+                writeln!(f, "\tFunction    Locals")?;
+                for (function_index, locals_count) in &self.function_locals {
+                    writeln!(f, "\t{:<10}{:#8}", self.function_label(function_index), locals_count)?;
+                }
+            }
+
+            if !self.function_complexity.is_empty() {
+                let mut complexity: Vec<(&usize, &u32)> = self.function_complexity.iter().collect();
+                complexity.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+                writeln!(f, "\nComplexity:")?;
+                writeln!(f, "\tFunction  Complexity")?;
+                complexity.truncate(self.top_limit());
+                for (function_index, function_complexity) in complexity {
+                    writeln!(f, "\t{:<10}{:#10}", self.function_label(function_index),
+                             function_complexity)?;
+                }
+            }
+
+            if !self.br_table_targets.is_empty() {
+                let total_targets: usize = self.br_table_targets.iter().sum();
+                writeln!(f, "\nBrTable Count: {}", self.br_table_targets.len())?;
+                writeln!(f, "BrTable Max Targets: {}",
+                         self.br_table_targets.iter().max().unwrap_or(&0))?;
+                writeln!(f, "BrTable Average Targets: {:.1}",
+                         total_targets as f64 / self.br_table_targets.len() as f64)?;
+            }
+
+            if !self.control_flow_stats.is_empty() {
+                writeln!(f, "\nControl Flow:")?;
+                writeln!(f, "\tOperator             Count")?;
+                for (opname, count) in &self.control_flow_stats {
+                    writeln!(f, "\t{:#018}{:#8}", opname, count)?;
+                }
+            }
+
+            if self.dead_instruction_count > 0 {
+                writeln!(f, "\nDead Instructions: {}", self.dead_instruction_count)?;
+            }
+
+            if !self.memory_grow_sites.is_empty() {
+                writeln!(f, "\nMemory Grow Sites:")?;
+                for (index, count) in &self.memory_grow_sites {
+                    writeln!(f, " {:<40}{:#8}", self.function_label(index), count)?;
+                }
+            }
+
+            if self.include_function_call_tree {
+                writeln!(f, "\nCall Tree:")?;
+                match self.selected_function {
+                    Some(index) => {
+                        let name = self.exported_functions.get(&index).map(|s| s.as_str())
+                            .unwrap_or("");
+                        self.print_call_tree(&index, name, f)?;
+                    },
+                    None => {
+                        // in index order, as the order of the calls map is not deterministic
+                        for (index, name) in &self.exported_functions {
+                            if self.static_function_calls.contains_key(index) {
+                                self.print_call_tree(index, name, f)?;
+                            }
+                        }
+                    },
+                }
+
+                writeln!(f, "Max Call Depth: {}", self.max_call_depth)?;
+                if !self.recursive_functions.is_empty() {
+                    writeln!(f, "Recursive ({}): {}", self.recursive_functions.len(),
+                             RangeVec::from(&self.recursive_functions))?;
+                    self.print_function_names(&self.recursive_functions, f)?;
+                }
+            }
+
+            if self.include_operators {
+                writeln!(f, "\nOperators:")?;
+                writeln!(f, "Operators Count: {}", self.operator_count)?;
+                writeln!(f, "Operator Usage:")?;
+                writeln!(f, "\tOperator             Count")?;
+                for (opname, count) in &self.sorted_operator_usage {
+                    writeln!(f, "\t{:#018}{:#8}", opname, count)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Analyze the parsed [Module], or [crate::OwnedModule], to see what sections it has and
+/// operators it uses
+pub fn analyze(module: &impl AsModule,
+               include_sections: bool,
+               include_functions: bool,
                include_operators: bool,
                include_function_call_tree: bool,
 ) -> Result<Analysis> {
+    analyze_with_jobs(module, include_sections, include_functions, include_operators,
+                      include_function_call_tree, 1)
+}
+
+/// Analyze the parsed [Module] as [analyze] does, analyzing the function bodies in parallel
+/// on `jobs` threads when it is more than 1. The analysis is the same whatever `jobs` is
+pub fn analyze_with_jobs(module: &impl AsModule,
+                         include_sections: bool,
+                         include_functions: bool,
+                         include_operators: bool,
+                         include_function_call_tree: bool,
+                         jobs: usize,
+) -> Result<Analysis> {
+    let module = module.as_module();
+    if module.kind == ModuleKind::Component &&
+        (include_functions || include_operators || include_function_call_tree) {
+        return Err(Error::UnsupportedComponent("Function, operator and call-tree analysis is \
+            only supported for core modules, only the sections of a component are analyzed"
+            .into()));
+    }
+
     let mut analysis = Analysis {
+        module_kind: module.kind,
+        version: module.version,
+        file_size: module.file_size,
         include_sections,
         include_functions,
         include_operators,
@@ -389,11 +2119,21 @@ pub fn analyze(module: &Module,
         ..Default::default() };
 
     let mut function_index = 0;
+    // the bodies to analyze in parallel, with the index of the first one
+    let mut deferred_bodies = vec![];
+    let mut first_deferred_index = 0;
     for payload in &module.payloads {
         #[allow(unused_variables)]
         match payload {
             CodeSectionStart { count, range, size } =>
                 analysis.add_section("CodeSectionStart", Some(*count), range)?,
+            CodeSectionEntry(function_body) if jobs > 1 => {
+                if deferred_bodies.is_empty() {
+                    first_deferred_index = function_index;
+                }
+                deferred_bodies.push(function_body);
+                function_index += 1;
+            },
             CodeSectionEntry(function_body) => analysis.add_function(function_body,
                                                                      &mut function_index)?,
             ComponentSection { parser, range } =>
@@ -408,39 +2148,40 @@ pub fn analyze(module: &Module,
                 analysis.add_section("ComponentCanonicalSection", None, &section.range())?,
             ComponentStartSection { start, range } =>
                 analysis.add_section("ComponentStartSection", None, range)?,
-            ComponentImportSection(section) =>
-                analysis.add_section("ComponentImportSection", None, &section.range())?,
+            ComponentImportSection(section) => {
+                analysis.add_section("ComponentImportSection", None, &section.range())?;
+                for import in module.section_items(section)?.flatten() {
+                    analysis.detect_wasi(import.name.as_str());
+                }
+            },
             ComponentExportSection(section) =>
                 analysis.add_section("ComponentExportSection", None, &section.range())?,
             CoreTypeSection(section) =>
                 analysis.add_section("CoreTypeSection", None, &section.range())?,
-            CustomSection(section) =>
-                analysis.add_section("CustomSection", None, &section.range())?,
+            CustomSection(reader) => analysis.add_custom(reader)?,
             DataCountSection { count, range } =>
                 analysis.add_section("DataCountSection", Some(*count), range)?,
-            DataSection(section) =>
-                analysis.add_section("DataSection", Some(section.count()), &section.range())?,
-            ElementSection(reader) => analysis.add_elements(reader)?,
-            ExportSection(reader) => analysis.add_exports(reader)?,
-            FunctionSection(section) =>
-                analysis.add_section("FunctionSection", Some(section.count()), &section.range())?,
-            GlobalSection(section) =>
-                analysis.add_section("GlobalSection", Some(section.count()), &section.range())?,
-            ImportSection(reader) => analysis.add_imports(reader, &mut function_index)?,
+            DataSection(reader) => analysis.add_data(module, reader)?,
+            ElementSection(reader) => analysis.add_elements(module, reader)?,
+            ExportSection(reader) => analysis.add_exports(module, reader)?,
+            FunctionSection(reader) => analysis.add_functions(module, reader)?,
+            GlobalSection(reader) => analysis.add_globals(module, reader)?,
+            ImportSection(reader) => analysis.add_imports(module, reader, &mut function_index)?,
             InstanceSection(section) =>
                 analysis.add_section("InstanceSection", Some(section.count()), &section.range())?,
-            MemorySection(section) =>
-                analysis.add_section("MemorySection", Some(section.count()), &section.range())?,
+            MemorySection(reader) => analysis.add_memories(module, reader)?,
             ModuleSection { parser, range } =>
                 analysis.add_section("ModuleSection", None, range)?,
-            StartSection { func, range } =>
-                analysis.add_section("StartSection", None, range)?,
-            TableSection(section) =>
-                analysis.add_section("TableSection", Some(section.count()), &section.range())?,
-            TagSection(section) =>
-                analysis.add_section("TagSection", Some(section.count()), &section.range())?,
-            TypeSection(section) =>
-                analysis.add_section("TypeSection", Some(section.count()), &section.range())?,
+            StartSection { func, range } => {
+                analysis.start_function = Some(*func as usize);
+                analysis.add_section("StartSection", None, range)?
+            },
+            TableSection(reader) => analysis.add_tables(module, reader)?,
+            TagSection(section) => {
+                analysis.add_tags(section.count());
+                analysis.add_section("TagSection", Some(section.count()), &section.range())?
+            },
+            TypeSection(reader) => analysis.add_types(module, reader)?,
             UnknownSection { id, contents, range } =>
                 analysis.add_section("UnknownSection", None, range)?,
             Version { num, encoding, range } =>
@@ -449,6 +2190,14 @@ pub fn analyze(module: &Module,
         }
     }
 
+    if !deferred_bodies.is_empty() {
+        analysis.add_functions_in_parallel(&deferred_bodies, first_deferred_index, jobs)?;
+    }
+
+    if module.kind == ModuleKind::CoreModule {
+        analysis.check_section_order(&module.sections()?);
+    }
+
     analysis.post_process();
 
     Ok(analysis)
@@ -457,9 +2206,9 @@ pub fn analyze(module: &Module,
 #[cfg(test)]
 mod test {
     use std::fs;
-    use std::process::Command;
     use std::path::PathBuf;
     use crate::analysis::RangeVec;
+    use crate::parse::ModuleKind;
     use crate::analysis::RangeVecEntry::{RangeEntry, SingleEntry};
 
     #[test]
@@ -471,6 +2220,149 @@ mod test {
                                          RangeEntry(9..=10))));
     }
 
+    #[test]
+    fn test_single_call_recorded() {
+        let mut analysis = super::Analysis::default();
+        analysis.add_function_call(1, 2);
+        assert_eq!(analysis.static_function_calls.get(&1), Some(&vec!(2)));
+        analysis.add_function_call(1, 3);
+        analysis.add_function_call(1, 2);
+        assert_eq!(analysis.static_function_calls.get(&1), Some(&vec!(2, 3)));
+    }
+
+    #[test]
+    fn test_empty_ranges() {
+        let ranges = RangeVec::from(&vec!());
+        assert_eq!(ranges, RangeVec(vec!()));
+        assert_eq!(ranges.to_string(), "[]");
+    }
+
+    #[test]
+    fn test_single_entry_ranges() {
+        let ranges = RangeVec::from(&vec!(3));
+        assert_eq!(ranges, RangeVec(vec!(SingleEntry(3))));
+        assert_eq!(ranges.to_string(), "[3]");
+        assert_eq!(RangeVec::from(&vec!(1, 2, 4, 5, 7)).to_string(), "[1..2, 4..5, 7]");
+    }
+
+    #[test]
+    fn test_section_columns_aligned() {
+        let sections = [
+            super::Section { section_type: "TypeSection".into(), header_location: 8,
+                item_count: Some(3), range: 10..30, size: 20, percentage: 2.0 },
+            super::Section { section_type: "CustomSection".into(), header_location: 30,
+                item_count: None, range: 32..1030, size: 998, percentage: 99.8 },
+        ];
+        let lines: Vec<String> = sections.iter().map(|section| section.to_string()).collect();
+        assert_eq!(lines[0].len(), lines[1].len());
+        for column in [" : ", " - ", "%"] {
+            assert_eq!(lines[0].find(column), lines[1].find(column), "column '{}'", column);
+        }
+        assert_eq!(lines[0].find("TypeSection"), lines[1].find("CustomSection"));
+        assert!(lines[1].starts_with("0x00000000001e : "));
+    }
+
+    #[test]
+    fn test_operator_names() {
+        use wasmparser::{MemArg, Operator};
+        assert_eq!(super::operator_name(&Operator::Nop), "Nop");
+        assert_eq!(super::operator_name(&Operator::LocalGet { local_index: 3 }), "LocalGet");
+        let memarg = MemArg { align: 2, max_align: 2, offset: 16, memory: 0 };
+        assert_eq!(super::operator_name(&Operator::I32Load { memarg }), "I32Load");
+        assert_eq!(super::operator_name(&Operator::V128Load32Lane { memarg, lane: 1 }),
+                   "V128Load32Lane");
+    }
+
+    #[test]
+    fn test_call_depth_recursion() {
+        let mut analysis = super::Analysis {
+            include_function_call_tree: true,
+            ..Default::default() };
+        analysis.exported_functions.insert(0, "main".into());
+        analysis.static_function_calls.insert(0, vec!(1));
+        analysis.static_function_calls.insert(1, vec!(2, 3));
+        analysis.static_function_calls.insert(2, vec!(1));
+        analysis.static_function_calls.insert(3, vec!(4));
+        analysis.post_process();
+        assert_eq!(analysis.max_call_depth, 3);
+        assert_eq!(analysis.recursive_functions, vec!(1, 2));
+    }
+
+    #[test]
+    fn test_call_depth_deep_diamonds() {
+        // a chain of diamonds, where each function calls two others that both call the next,
+        // has too many paths through it to follow them all
+        let mut analysis = super::Analysis {
+            include_function_call_tree: true,
+            ..Default::default() };
+        analysis.exported_functions.insert(0, "main".into());
+        let diamonds = 100;
+        for diamond in 0..diamonds {
+            let top = diamond * 3;
+            analysis.static_function_calls.insert(top, vec!(top + 1, top + 2));
+            analysis.static_function_calls.insert(top + 1, vec!(top + 3));
+            analysis.static_function_calls.insert(top + 2, vec!(top + 3));
+        }
+        // the last function only calls itself
+        analysis.static_function_calls.insert(diamonds * 3, vec!(diamonds * 3));
+        analysis.post_process();
+        assert_eq!(analysis.max_call_depth, diamonds * 2);
+        assert_eq!(analysis.recursive_functions, vec!(diamonds * 3));
+    }
+
+    #[test]
+    fn test_unused_imports() {
+        let mut analysis = super::Analysis::default();
+        analysis.imported_functions.insert(0, "called".into());
+        analysis.imported_functions.insert(1, "in_table".into());
+        analysis.imported_functions.insert(2, "unused".into());
+        analysis.static_function_calls.insert(3, vec!(0));
+        analysis.dynamic_dispatch_functions.push(1);
+        analysis.post_process();
+        assert_eq!(analysis.unused_imports, vec!(2));
+        assert_eq!(analysis.import_callers.get(&0), Some(&vec!(3)));
+        assert_eq!(analysis.import_callers.len(), 1);
+    }
+
+    #[test]
+    fn test_callers_of() {
+        let mut analysis = super::Analysis::default();
+        analysis.static_function_calls.insert(0, vec!(2));
+        analysis.static_function_calls.insert(1, vec!(2, 0));
+        analysis.static_function_calls.insert(2, vec!(1));
+        assert_eq!(analysis.callers_of(2), vec!(0, 1));
+        assert!(analysis.callers_of(3).is_empty());
+        let callers = super::Callers { analysis: &analysis, index: 2 }.to_string();
+        assert!(callers.contains("Cyclic call"));
+        let callers = super::Callers { analysis: &analysis, index: 3 }.to_string();
+        assert!(callers.contains("has no callers"));
+    }
+
+    #[test]
+    fn test_reachable_functions() {
+        let mut analysis = super::Analysis::default();
+        analysis.exported_functions.insert(0, "main".into());
+        analysis.start_function = Some(5);
+        analysis.dynamic_dispatch_functions.push(3);
+        analysis.static_function_calls.insert(0, vec!(1));
+        analysis.static_function_calls.insert(3, vec!(4));
+        analysis.static_function_calls.insert(7, vec!(8));
+        let reachable: Vec<usize> = analysis.reachable_functions([]).into_iter().collect();
+        assert_eq!(reachable, vec!(0, 1, 3, 4, 5));
+    }
+
+    #[test]
+    fn test_size_histogram() {
+        let analysis = super::Analysis {
+            function_sizes: vec!((0, 10), (1, 50), (2, 51), (3, 1000)),
+            ..Default::default() };
+        assert_eq!(analysis.size_histogram(), vec!(("0-50".to_string(), 2),
+                                                   ("51-100".to_string(), 1),
+                                                   ("101-250".to_string(), 0),
+                                                   ("251-500".to_string(), 0),
+                                                   ("501+".to_string(), 1)));
+    }
+
     #[test]
     fn test_to_ranges_end_single() {
         let ranges = RangeVec::from(&vec!(1, 2, 4, 5, 7, 9));
@@ -480,31 +2372,650 @@ mod test {
                                          SingleEntry(9))));
     }
 
+    // The path of the file called `test_file_name` in the test files directory
     fn test_file(test_file_name: &str) -> PathBuf {
-        let source = PathBuf::from(&format!("{}/tests/test_files/{}",
-                                            env!("CARGO_MANIFEST_DIR"),
-        test_file_name));
-        let mut wasm = source.clone();
-        wasm.set_extension("wasm");
-        let _ = fs::remove_file(&wasm);
-        let mut compiler = Command::new("wat2wasm");
-        compiler.arg(source);
-        compiler.arg("-o");
-        compiler.arg(&wasm);
-        compiler.output().expect("wat2wasm compile failed");
-        wasm
+        PathBuf::from(&format!("{}/tests/test_files/{}", env!("CARGO_MANIFEST_DIR"),
+                               test_file_name))
+    }
+
+    // Compile the WebAssembly text in the test file called `test_file_name` into WASM bytes
+    fn test_wasm(test_file_name: &str) -> Vec<u8> {
+        let text = fs::read_to_string(test_file(test_file_name)).expect("Could not read test file");
+        crate::parse::wat_to_wasm(&text).expect("Could not compile test file")
+    }
+
+    // Compile the test file called `test_file_name` and analyze it, including the same parts as
+    // the arguments of the same name to [super::analyze]
+    fn analyze_test_file(test_file_name: &str, include_sections: bool, include_functions: bool,
+                         include_operators: bool, include_function_call_tree: bool)
+                         -> super::Analysis {
+        let module = super::Module::parse(&test_file(test_file_name))
+            .expect("Could not parse test wasm");
+        super::analyze(&module, include_sections, include_functions, include_operators,
+                       include_function_call_tree)
+            .expect("Analysis of test file failed")
+    }
+
+    #[test]
+    fn test_analyze_component() {
+        let wasm = test_file("component.wat");
+        let buf = test_wasm("component.wat");
+        let module = super::Module::from_source(&wasm, &buf).expect("Could not parse test wasm");
+        assert_eq!(module.kind, ModuleKind::Component);
+        match super::analyze(&module, false, true, true, false) {
+            Err(crate::errors::Error::UnsupportedComponent(message)) =>
+                assert!(message.contains("only supported for core modules"), "{}", message),
+            Err(e) => panic!("Unexpected error: {}", e),
+            Ok(_) => panic!("Analyzed the functions of a component"),
+        }
+
+        let analysis = super::analyze(&module, true, false, false, false)
+            .expect("Analysis of component sections failed");
+        assert!(analysis.sections.iter().any(|section| section.section_type == "ModuleSection"));
+    }
+
+    #[test]
+    fn test_analyze_owned() {
+        let wasm = test_file("hello_web.wat");
+        let mut cache = std::collections::HashMap::new();
+        cache.insert(wasm.clone(), super::Module::parse(&wasm)
+            .expect("Could not parse test wasm"));
+
+        // the cached module is analyzed without parsing it again
+        let owned = cache.get(&wasm).expect("Module not in cache");
+        let module = owned.module();
+        // the size is that of the file on disk, not of the WASM compiled from its text
+        assert_eq!(module.file_size, fs::metadata(&wasm).expect("No test file").len());
+        assert!(module.source.ends_with("hello_web.wat"));
+        let analysis = super::analyze(owned, true, true, true, true)
+            .expect("Analysis of wasm file failed");
+        assert_eq!(analysis.implemented_function_count, 2);
+        let moved = cache.remove(&wasm).expect("Module not in cache");
+        let moved_analysis = super::analyze(&moved, true, true, true, true)
+            .expect("Analysis of moved module failed");
+        assert_eq!(moved_analysis.to_string(), analysis.to_string());
+    }
+
+    #[test]
+    fn test_call_indirect_sites() {
+        let analysis = analyze_test_file("duplicate_signatures.wat", false, true, false, false);
+        assert_eq!(analysis.call_indirect_sites, vec!((2, 3, 0), (2, 2, 0)));
+        let output = analysis.to_string();
+        assert!(output.contains("Indirect Call Sites (2):"));
+        assert!(output.contains(" type     3 (i32) -> i32"));
+    }
+
+    #[test]
+    fn test_imports_and_locals_indices() {
+        let analysis = analyze_test_file("imports_and_locals.wat", false, true, false, false);
+        assert_eq!(analysis.implemented_functions(), 2..5);
+        assert_eq!(analysis.function_signature(0), Some("(i32)".into()));
+        assert_eq!(analysis.function_signature(1), Some("() -> i32".into()));
+        assert_eq!(analysis.function_signature(2), Some("()".into()));
+        assert_eq!(analysis.function_signature(5), None);
+        assert!(analysis.to_string().contains("    1 'now' () -> i32"));
+        assert_eq!(analysis.exported_functions.keys().collect::<Vec<_>>(), vec!(&2));
+        assert_eq!(analysis.statically_called_functions, vec!(0, 1, 3));
+        assert_eq!(analysis.uncalled_functions, vec!(4));
+        assert!(analysis.unused_imports.is_empty());
+        assert_eq!(analysis.import_groups.get("env").map(|group| group.functions), Some(2));
+        let mut locals = analysis.function_locals.clone();
+        locals.sort();
+        assert_eq!(locals, vec!((2, 1), (3, 0), (4, 2)));
+    }
+
+    #[test]
+    fn test_externals() {
+        let analysis = analyze_test_file("externals.wat", false, true, false, false);
+        assert_eq!(analysis.imported_memories.get(&0), Some(&"memory".to_string()));
+        assert_eq!(analysis.imported_globals.get(&0), Some(&"stack_pointer".to_string()));
+        assert_eq!(analysis.imported_tables.get(&0), Some(&"table".to_string()));
+        assert_eq!(analysis.exported_memories.get(&0), Some(&vec!("memory".to_string())));
+        assert_eq!(analysis.exported_globals.len(), 2);
+        assert_eq!(analysis.exported_globals.get(&1), Some(&vec!("counter".to_string())));
+        assert_eq!(analysis.exported_tables.get(&1), Some(&vec!("own_table".to_string())));
+        assert_eq!(analysis.exported_functions.len(), 1);
+        let output = analysis.to_string();
+        assert!(output.contains("Imported Memories (1):"));
+        assert!(output.contains(" env: 1 function, 1 memory, 1 global, 1 table\n"));
+        assert!(output.contains("Functions:\nImport Summary: 1 function imports, 1 memory imports, \
+            1 global imports, 1 table imports\n"));
+        assert!(output.contains("Exported Globals (2):"));
+    }
+
+    #[test]
+    fn test_externals_exported_twice() {
+        let analysis = analyze_test_file("memory_exported_twice.wat", false, false, false, false);
+        assert_eq!(analysis.exported_memories.len(), 1);
+        assert_eq!(analysis.exported_memories.get(&0),
+                   Some(&vec!("memory".to_string(), "heap".to_string())));
+        assert_eq!(analysis.exported_globals.get(&0),
+                   Some(&vec!("flag".to_string(), "enabled".to_string())));
+        let output = analysis.to_string();
+        assert!(output.contains("Exported Memories (1):\n     0 'memory', 'heap'\n"), "{}", output);
+    }
+
+    #[test]
+    fn test_producers() {
+        let mut language = wasm_encoder::ProducersField::new();
+        language.value("Rust", "");
+        let mut processed_by = wasm_encoder::ProducersField::new();
+        processed_by.value("rustc", "1.70.0");
+        processed_by.value("wasm-bindgen", "0.2.87");
+        let mut producers = wasm_encoder::ProducersSection::new();
+        producers.field("language", &language);
+        producers.field("processed-by", &processed_by);
+        let mut module = wasm_encoder::Module::new();
+        module.section(&producers);
+        // a malformed producers section is ignored
+        module.section(&wasm_encoder::CustomSection { name: "producers".into(), data: vec!(5).into() });
+        let buf = module.finish();
+
+        let module = super::Module::from_bytes(&buf).expect("Could not parse test wasm");
+        let analysis = super::analyze(&module, false, false, false, false)
+            .expect("Analysis of wasm file failed");
+        assert_eq!(analysis.producers.len(), 2);
+        assert_eq!(analysis.producers[1].1[1], ("wasm-bindgen".into(), "0.2.87".into()));
+        let output = analysis.to_string();
+        assert!(output.contains(" language: Rust\n"));
+        assert!(output.contains(" processed-by: rustc 1.70.0, wasm-bindgen 0.2.87\n"));
+    }
+
+    #[test]
+    fn test_debug_info() {
+        let mut module = wasm_encoder::Module::new();
+        module.section(&wasm_encoder::CustomSection { name: ".debug_info".into(),
+            data: vec!(0; 100).into() });
+        module.section(&wasm_encoder::CustomSection { name: "name".into(), data: vec!().into() });
+        module.section(&wasm_encoder::CustomSection { name: ".debug_line".into(),
+            data: vec!(0; 20).into() });
+        let buf = module.finish();
+
+        let module = super::Module::from_bytes(&buf).expect("Could not parse test wasm");
+        let analysis = super::analyze(&module, false, false, false, false)
+            .expect("Analysis of wasm file failed");
+        assert!(analysis.has_debug_info);
+        // the size of each section includes its name
+        assert_eq!(analysis.debug_sections, vec!((".debug_info".into(), 112),
+                                                 (".debug_line".into(), 32)));
+        assert_eq!(analysis.debug_info_size, 144);
+        assert!(analysis.to_string().contains("Debug Info (144 bytes):\n .debug_info"));
+
+        let buf = wasm_encoder::Module::new().finish();
+        let module = super::Module::from_bytes(&buf).expect("Could not parse test wasm");
+        let analysis = super::analyze(&module, false, false, false, false)
+            .expect("Analysis of wasm file failed");
+        assert!(!analysis.has_debug_info);
+        assert!(!analysis.to_string().contains("Debug Info"));
+    }
+
+    #[test]
+    fn test_demangle() {
+        assert_eq!(super::demangle("_ZN4core3fmt5write17h0123456789abcdefE"), "core::fmt::write");
+        assert_eq!(super::demangle("_RNvCs1234_7mycrate3foo"), "mycrate::foo");
+        assert_eq!(super::demangle("_Z3fooi"), "foo(int)");
+        assert_eq!(super::demangle("main"), "main");
+        assert_eq!(super::demangle("_Zgarbage"), "_Zgarbage");
+    }
+
+    #[test]
+    fn test_demangle_names() {
+        let mut analysis = super::Analysis::default();
+        analysis.imported_functions.insert(0, "_Z3logPKc".into());
+        analysis.function_names.insert(0, "_Z3logPKc".into());
+        analysis.function_names.insert(1, "_ZN7mycrate4main17h0123456789abcdefE".into());
+        analysis.function_names.insert(2, "start".into());
+        analysis.demangle_names();
+        assert_eq!(analysis.imported_functions.get(&0).map(|s| s.as_str()), Some("log(char const*)"));
+        assert_eq!(analysis.function_names.get(&1).map(|s| s.as_str()), Some("mycrate::main"));
+        assert_eq!(analysis.function_names.get(&2).map(|s| s.as_str()), Some("start"));
+    }
+
+    #[test]
+    fn test_module_hash() {
+        let mut analysis = super::Analysis::default();
+        assert!(!analysis.to_string().contains("Module Hash"));
+        analysis.hash(b"abc");
+        assert_eq!(analysis.module_hash,
+                   "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+        assert!(analysis.to_string().starts_with("Module Hash (SHA-256): ba7816bf"));
     }
 
     #[test]
     fn test_analyze_hello_web() {
         let wasm = test_file("hello_web.wat");
-        let buf: Vec<u8> = fs::read(&wasm).expect("Could not read wasm file");
-        let module = super::Module::parse(&wasm, &buf).expect("Could not parse test wasm");
+        let buf = test_wasm("hello_web.wat");
+        let module = super::Module::from_source(&wasm, &buf).expect("Could not parse test wasm");
         assert_eq!(module.version, 1);
         let analysis = super::analyze(&module, true, true, true, true)
             .expect("Analysis of wasm file failed");
         assert_eq!(analysis.exported_functions.len(), 1);
         assert_eq!(analysis.implemented_function_count, 2);
-        let _ = fs::remove_file(&wasm);
+        assert_eq!(analysis.function_sizes.len(), 2);
+        assert!(analysis.function_sizes.iter().all(|(index, _)| *index > 0));
+        assert_eq!(analysis.function_locals, vec!((1, 0), (2, 0)));
+        assert_eq!(analysis.function_complexity.get(&1), Some(&1));
+        assert!(!analysis.uses_simd);
+        assert!(!analysis.uses_bulk_memory);
+        assert!(!analysis.uses_threads);
+        assert!(!analysis.uses_multi_value);
+        assert!(!analysis.uses_reference_types);
+        assert!(!analysis.uses_floating_point);
+        assert!(!analysis.uses_memory64);
+        assert!(!analysis.uses_tail_calls);
+        assert!(!analysis.uses_exceptions);
+    }
+
+    #[test]
+    fn test_analyze_csv() {
+        let analysis = analyze_test_file("hello_web.wat", true, false, false, false);
+        let csv = analysis.to_csv();
+        let mut lines = csv.lines();
+        assert_eq!(lines.next(),
+                   Some("section_type,header_start,content_start,content_end,size,item_count"));
+        assert_eq!(lines.next(), Some("Version,0,0,7,8,"));
+        assert_eq!(csv.lines().count(), analysis.sections.len() + 1);
+    }
+
+    #[test]
+    fn test_analyze_markdown() {
+        let analysis = analyze_test_file("hello_web.wat", true, true, false, false);
+        let markdown = analysis.to_markdown();
+        let lines: Vec<&str> = markdown.lines().collect();
+        assert_eq!(lines[0], "## Sections");
+        assert_eq!(lines[3], "|------|---:|---:|---:|---:|---:|---:|");
+        assert_eq!(lines[4], "| Version | 0x0 | 0x0 | 0x7 | 8 | 5.7 |  |");
+        assert!(markdown.contains("\n## Functions\n\nImported: 1, Implemented: 2, Exported: 1\n"));
+        assert!(markdown.contains("\n|---:|------|---:|---:|---:|\n"));
+        assert_eq!(markdown.lines().filter(|line| line.starts_with("| ")).count(),
+                   analysis.sections.len() + 1 + analysis.function_sizes.len() + 1);
+    }
+
+    #[test]
+    fn test_section_percentages() {
+        let analysis = analyze_test_file("hello_web.wat", true, false, false, false);
+        let total: f64 = analysis.sections.iter().map(|section| section.percentage).sum();
+        assert!(total > 0.0 && total <= 100.0);
+        let version = &analysis.sections[0];
+        assert_eq!(version.percentage,
+                   version.size as f64 * 100.0 / analysis.sections_size_total as f64);
+    }
+
+    #[test]
+    fn test_analyze_duplicate_types() {
+        let analysis = analyze_test_file("duplicate_types.wat", true, false, false, false);
+        assert_eq!(analysis.types.len(), 4);
+        assert_eq!(analysis.duplicate_type_count, 1);
+        assert_eq!(analysis.duplicate_types, vec!((2, 0)));
+    }
+
+    #[test]
+    fn test_select_function() {
+        let mut analysis = analyze_test_file("hello_web.wat", false, true, true, true);
+        assert!(matches!(analysis.select_function("nonexistent"),
+                         Err(crate::errors::Error::FunctionNotFound { .. })));
+        assert!(matches!(analysis.select_function("3"),
+                         Err(crate::errors::Error::FunctionNotFound { function_count: 3, .. })));
+        assert_eq!(analysis.select_function("helloWorld").expect("Could not select"), 1);
+        assert_eq!(analysis.function_sizes.len(), 1);
+        assert_eq!(analysis.operator_count, 4);
+    }
+
+    #[test]
+    fn test_analyze_entry_point() {
+        let analysis = analyze_test_file("entry_point.wat", false, true, false, true);
+        assert_eq!(analysis.entry_point, Some((2, "_start".into())));
+        assert_eq!(analysis.other_entry_point, Some((1, "main".into())));
+        assert!(analysis.to_string().starts_with(
+            "Entry Point: '_start' (function #2), 'main' (function #1) is also exported\n"));
+        // only the function that isn't called from an entry point is uncalled
+        assert_eq!(analysis.uncalled_functions, vec!(3));
+    }
+
+    #[test]
+    fn test_analyze_start_function() {
+        let analysis = analyze_test_file("start.wat", false, true, false, false);
+        assert_eq!(analysis.start_function, Some(0));
+        assert!(!analysis.to_string().contains("Uncalled"));
+    }
+
+    #[test]
+    fn test_analyze_multi_value() {
+        let analysis = analyze_test_file("multi_value.wat", false, false, false, false);
+        assert!(analysis.uses_multi_value);
+    }
+
+    #[test]
+    fn test_analyze_floating_point() {
+        let analysis = analyze_test_file("floating_point.wat", false, false, false, false);
+        assert!(analysis.uses_floating_point);
+        assert_eq!(analysis.floating_point_operator_count, 6);
+        assert!(analysis.to_string().contains("Floating Point: used (6 ops)"));
+    }
+
+    #[test]
+    fn test_control_flow_stats() {
+        let analysis = analyze_test_file("control_flow.wat", false, true, false, false);
+        let expected: Vec<(&str, u64)> = vec!(("Block", 2), ("Br", 1), ("BrIf", 1),
+            ("BrTable", 1), ("Call", 1), ("Else", 1), ("If", 1), ("Loop", 1), ("Return", 1),
+            ("Unreachable", 1));
+        let stats: Vec<(&str, u64)> = analysis.control_flow_stats.iter()
+            .map(|(opname, count)| (opname.as_str(), *count)).collect();
+        assert_eq!(stats, expected);
+        assert!(analysis.to_string().contains("Control Flow:"));
+    }
+
+    #[test]
+    fn test_analyze_reference_types() {
+        let analysis = analyze_test_file("reference_types.wat", false, false, false, false);
+        assert!(analysis.uses_reference_types);
+        assert_eq!(analysis.reference_type_operator_count, 2);
+    }
+
+    #[test]
+    fn test_analyze_function_names() {
+        let analysis = analyze_test_file("hello_web.wat", true, true, false, true);
+        assert_eq!(analysis.function_names.get(&0).map(|s| s.as_str()), Some("log"));
+    }
+
+    #[test]
+    fn test_analyze_tables() {
+        let analysis = analyze_test_file("tables.wat", true, true, false, false);
+        assert_eq!(analysis.tables.len(), 2);
+        assert!(analysis.tables[0].imported);
+        assert_eq!(analysis.tables[1].initial, 2);
+        assert_eq!(analysis.tables[1].maximum, Some(10));
+    }
+
+    #[test]
+    fn test_analyze_simd() {
+        let analysis = analyze_test_file("simd.wat", false, false, false, false);
+        assert!(analysis.uses_simd);
+        assert_eq!(analysis.simd_operator_count, 3);
+        assert!(!analysis.uses_relaxed_simd);
+        assert!(analysis.to_string().contains("Relaxed SIMD: not used"));
+    }
+
+    #[test]
+    fn test_analyze_gc() {
+        // a type section with an array of mutable i32
+        let buf = b"\0asm\x01\0\0\0\x01\x04\x01\x5e\x7f\x01";
+        let module = super::Module::from_bytes(buf).expect("Could not parse test wasm");
+        let mut analysis = super::analyze(&module, false, false, false, false)
+            .expect("Analysis of wasm file failed");
+        assert!(analysis.uses_gc);
+        analysis.detect_features(&wasmparser::Operator::I31New, "I31New");
+        let features: Vec<&str> = analysis.gc_features.iter().map(|s| s.as_str()).collect();
+        assert_eq!(features, vec!("array types", "i31 references"));
+        assert!(analysis.to_string().contains("WARNING GC: used (array types, i31 references)"));
+
+        let analysis = analyze_test_file("hello_web.wat", false, false, false, false);
+        assert!(!analysis.uses_gc);
+        assert!(analysis.to_string().contains("\nGC: not used"));
+    }
+
+    #[test]
+    fn test_analyze_relaxed_simd() {
+        let analysis = analyze_test_file("relaxed_simd.wat", false, false, false, false);
+        assert!(analysis.uses_relaxed_simd);
+        assert_eq!(analysis.relaxed_simd_operator_count, 1);
+        assert!(analysis.to_string().contains("Relaxed SIMD: used (1 ops)"));
+    }
+
+    #[test]
+    fn test_analyze_bulk_memory() {
+        let analysis = analyze_test_file("bulk_memory.wat", false, false, false, false);
+        assert!(analysis.uses_bulk_memory);
+        assert_eq!(analysis.bulk_memory_usage.get("MemoryCopy"), Some(&1));
+        assert_eq!(analysis.bulk_memory_usage.get("MemoryFill"), Some(&2));
+    }
+
+    #[test]
+    fn test_analyze_memory_grow() {
+        let analysis = analyze_test_file("memory_grow.wat", false, true, false, false);
+        assert!(analysis.uses_memory_grow);
+        assert_eq!(analysis.memory_grow_sites, std::collections::BTreeMap::from([(0, 2)]));
+        assert_eq!(analysis.memory_size_count, 1);
+        let text = analysis.to_string();
+        assert!(text.contains("memory.grow: used (2 sites in 1 functions, memory.size: 1 ops)"));
+        assert!(text.contains("Memory Grow Sites:"));
+
+        let analysis = analyze_test_file("sign_extension.wat", false, false, false, false);
+        assert!(!analysis.uses_memory_grow);
+        assert!(analysis.to_string().contains("memory.grow: not used"));
+    }
+
+    #[test]
+    fn test_analyze_sign_extension() {
+        let analysis = analyze_test_file("sign_extension.wat", false, false, false, false);
+        assert!(analysis.uses_sign_extension);
+        // i64.extend_i32_s is an MVP conversion, not a sign-extension operator
+        assert_eq!(analysis.sign_extension_operator_count, 2);
+        assert!(analysis.to_string().contains("Sign Extension: used (2 ops)"));
+    }
+
+    #[test]
+    fn test_analyze_nontrapping_float_to_int() {
+        let analysis = analyze_test_file("nontrapping_float_to_int.wat",
+                                         false, false, false, false);
+        assert!(analysis.uses_nontrapping_float_to_int);
+        assert_eq!(analysis.nontrapping_float_to_int_count, 2);
+        assert!(analysis.to_string().contains("Non-trapping Float-to-int: used (2 ops)"));
+
+        let analysis = analyze_test_file("floating_point.wat", false, false, false, false);
+        assert!(!analysis.uses_nontrapping_float_to_int);
+    }
+
+    #[test]
+    fn test_analyze_data_segments() {
+        let analysis = analyze_test_file("data_segments.wat", false, false, false, false);
+        assert_eq!(analysis.active_data_segments, 1);
+        assert_eq!(analysis.active_data_size, 6);
+        assert_eq!(analysis.passive_data_segments, vec!((1, 5), (2, 12)));
+        assert!(analysis.memory_init_segments.contains(&1));
+        let output = analysis.to_string();
+        assert!(output.contains(" Active: 1 (6 bytes)\n Passive: 2 (17 bytes)\n"));
+        assert!(output.contains("     2 passive segment of 12 bytes is not used by memory.init"));
+        assert!(!output.contains("     1 passive segment"));
+    }
+
+    #[test]
+    fn test_analyze_threads() {
+        let analysis = analyze_test_file("threads.wat", false, false, false, false);
+        assert!(analysis.uses_threads);
+        assert!(analysis.atomic_operators.contains("I32AtomicRmwAdd"));
+        assert!(analysis.uses_shared_memory);
+        assert!(analysis.to_string().contains("WARNING Shared Memory: used"));
+    }
+
+    #[test]
+    fn test_analyze_atomics_without_shared_memory() {
+        let analysis = analyze_test_file("atomics_unshared.wat", false, false, false, false);
+        assert!(analysis.uses_threads);
+        assert!(!analysis.uses_shared_memory);
+        assert!(analysis.to_string().contains("\nShared Memory: not used"));
+    }
+
+    #[test]
+    fn test_analyze_memory64() {
+        let analysis = analyze_test_file("memory64.wat", false, false, false, false);
+        assert!(analysis.uses_memory64);
+        assert!(analysis.to_string().contains("WARNING Memory64: used"));
+    }
+
+    #[test]
+    fn test_analyze_tail_calls() {
+        let analysis = analyze_test_file("tail_calls.wat", false, true, false, false);
+        assert!(analysis.uses_tail_calls);
+        assert_eq!(analysis.tail_call_count, 2);
+        // return_call_indirect is a tail call, not a call_indirect site
+        assert!(analysis.call_indirect_sites.is_empty());
+        assert_eq!(analysis.static_function_calls.get(&0), Some(&vec!(1)));
+        assert!(analysis.uncalled_functions.is_empty());
+    }
+
+    #[test]
+    fn test_analyze_exceptions() {
+        let analysis = analyze_test_file("exceptions.wat", false, false, false, false);
+        assert!(analysis.uses_exceptions);
+        assert_eq!(analysis.tag_count, 1);
+        assert_eq!(analysis.exception_operator_count, 3);
+    }
+
+    #[test]
+    fn test_analyze_wasi() {
+        let analysis = analyze_test_file("wasi.wat", false, false, false, false);
+        assert_eq!(analysis.wasi.as_deref(), Some("snapshot_preview1"));
+        assert!(analysis.to_string().contains("WASI: snapshot_preview1"));
+    }
+
+    #[test]
+    fn test_analyze_no_wasi() {
+        let analysis = analyze_test_file("imports_and_locals.wat", false, false, false, false);
+        assert!(analysis.wasi.is_none());
+        assert!(analysis.to_string().contains("WASI: none"));
+    }
+
+    #[test]
+    fn test_section_order() {
+        // a function section before the type section, with custom sections in between
+        let buf = b"\0asm\x01\0\0\0\x00\x02\x01a\x03\x01\x00\x00\x02\x01b\x01\x01\x00";
+        let module = super::Module::from_bytes(buf).expect("Could not parse test wasm");
+        let analysis = super::analyze(&module, true, false, false, false)
+            .expect("Analysis of wasm file failed");
+        assert_eq!(analysis.section_order_warnings,
+                   vec!("TypeSection at offset 0x15 is out of order after FunctionSection"));
+        assert!(analysis.to_string().contains("WARNING Section order: TypeSection"));
+    }
+
+    #[test]
+    fn test_section_order_valid() {
+        let analysis = analyze_test_file("wasi.wat", false, false, false, false);
+        assert!(analysis.section_order_warnings.is_empty());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_analyze_dead_instructions() {
+        let analysis = analyze_test_file("dead_code.wat", false, true, false, false);
+        // nop, i32.const and drop in the block, then block, nop and end after unreachable
+        // and the i32.const after return
+        assert_eq!(analysis.dead_instruction_count, 7);
+        assert!(analysis.to_string().contains("Dead Instructions: 7"));
+    }
+
+    #[test]
+    fn test_analyze_globals() {
+        let analysis = analyze_test_file("globals.wat", false, false, false, false);
+        let initializers: Vec<Option<&str>> = analysis.globals.iter()
+            .map(|global| global.initializer.as_deref()).collect();
+        assert_eq!(initializers, vec!(None, Some("i32.const 1048576"), Some("global.get 0"),
+                                      Some("f64.const 0.5")));
+        assert!(analysis.globals[1].mutable);
+        let output = analysis.to_string();
+        assert!(output.contains("Globals (4):\n     0 i32 (imported)\n"));
+        assert!(output.contains("     1 i32 mut = i32.const 1048576\n"));
+    }
+
+    #[test]
+    fn test_section_size_diff() {
+        let before_analysis = analyze_test_file("hello_web.wat", true, false, false, false);
+        let after_analysis = analyze_test_file("globals.wat", true, false, false, false);
+
+        let diff = super::SectionSizeDiff { before: &before_analysis, after: &after_analysis };
+        let sizes = diff.sizes();
+        assert_eq!(sizes[0], ("Version".into(), 8, 8));
+        // hello_web has no globals and globals has no code
+        assert!(sizes.iter().any(|(name, before, after)|
+            name == "GlobalSection" && *before == 0 && *after > 0));
+        assert!(sizes.iter().any(|(name, before, after)|
+            name == "CodeSectionStart" && *before > 0 && *after == 0));
+        let before_total: usize = sizes.iter().map(|(_, before, _)| before).sum();
+        assert_eq!(before_total, before_analysis.sections_size_total);
+        let output = diff.to_string();
+        assert!(output.starts_with("Section "));
+        assert!(output.contains("Version                          8         8        +0\n"));
+        assert!(output.lines().last().expect("No total").starts_with("Total "));
+    }
+
+    #[test]
+    fn test_oversized_functions() {
+        let analysis = analyze_test_file("imports_and_locals.wat", false, true, false, false);
+        let (largest, largest_size) = analysis.function_sizes[0];
+        let oversized = analysis.oversized_functions(largest_size - 1);
+        assert_eq!(oversized.len(), 1);
+        assert_eq!(oversized[0].0, largest);
+        assert_eq!(oversized[0].2, largest_size);
+        assert!(analysis.oversized_functions(largest_size).is_empty());
+    }
+
+    #[test]
+    fn test_sort_sections() {
+        let mut analysis = analyze_test_file("hello_web.wat", true, false, false, false);
+        let offsets: Vec<usize> = analysis.sections.iter()
+            .map(|section| section.header_location).collect();
+
+        analysis.sort_sections(super::SectionSort::Size);
+        assert!(analysis.sections.windows(2).all(|pair| pair[0].size >= pair[1].size));
+        analysis.sort_sections(super::SectionSort::Type);
+        assert!(analysis.sections.windows(2)
+            .all(|pair| pair[0].section_type <= pair[1].section_type));
+        analysis.sort_sections(super::SectionSort::Offset);
+        let sorted: Vec<usize> = analysis.sections.iter()
+            .map(|section| section.header_location).collect();
+        assert_eq!(sorted, offsets);
+    }
+
+    #[test]
+    fn test_filter_sections() {
+        let mut analysis = analyze_test_file("hello_web.wat", true, false, false, false);
+        // every section type used by the analysis is known
+        assert!(analysis.sections.iter()
+            .all(|section| super::SECTION_TYPES.contains(&section.section_type.as_str())));
+
+        let error = analysis.filter_sections(&["Code".into()]).expect_err("Filtered unknown type");
+        assert!(matches!(&error, crate::errors::Error::UnknownSectionType(section_type)
+            if section_type == "Code"));
+        assert!(error.to_string().starts_with("Unknown section type 'Code', valid types are: "));
+        assert!(error.to_string().contains("CodeSectionStart"));
+
+        analysis.filter_sections(&["CodeSectionStart".into(), "DataSection".into()])
+            .expect("Could not filter sections");
+        let types: Vec<&str> = analysis.sections.iter()
+            .map(|section| section.section_type.as_str()).collect();
+        assert_eq!(types, vec!("CodeSectionStart", "DataSection"));
+        let subtotal: usize = analysis.sections.iter()
+            .map(|section| section.range.end - section.header_location).sum();
+        let output = analysis.to_string();
+        assert!(output.contains(&format!("Subtotal Size (selected sections only): {}\n", subtotal)));
+        assert!(!output.contains("Total Size:"));
+    }
+
+    #[test]
+    fn test_analyze_with_jobs() {
+        let wasm = PathBuf::from(&format!("{}/tests/test_files/compare_switch_stripped.wasm",
+                                          env!("CARGO_MANIFEST_DIR")));
+        let buf: Vec<u8> = fs::read(&wasm).expect("Could not read wasm file");
+        let module = super::Module::from_source(&wasm, &buf).expect("Could not parse test wasm");
+        let sequential = super::analyze(&module, true, true, true, true)
+            .expect("Analysis of wasm file failed");
+        for jobs in [2, 3, 8] {
+            let parallel = super::analyze_with_jobs(&module, true, true, true, true, jobs)
+                .expect("Parallel analysis of wasm file failed");
+            assert_eq!(parallel.to_string(), sequential.to_string(), "with {} jobs", jobs);
+            assert_eq!(parallel.function_sizes, sequential.function_sizes);
+            assert_eq!(parallel.static_function_calls, sequential.static_function_calls);
+        }
+    }
+
+    #[test]
+    fn test_summary() {
+        let analysis = analyze_test_file("hello_web.wat", false, false, false, false);
+        let summary = analysis.summary();
+        let lines: Vec<&str> = summary.lines().collect();
+        let size = fs::metadata(test_file("hello_web.wat")).expect("No test file").len();
+        assert_eq!(lines[0], format!("Size: {} bytes, version 1, core module", size));
+        assert_eq!(lines[1], "Functions: 3 (1 imported, 2 implemented)");
+        assert_eq!(lines[2], "Imports: 2, Exports: 1");
+        assert!(lines[3].starts_with("Largest Section: DataSection ("));
+    }
+}
@@ -1,9 +1,248 @@
+use std::collections::HashMap;
+use std::io::Write;
 use std::path::Path;
+use sha2::{Digest, Sha256};
+use zstd::stream::Encoder;
+use wasmparser::CustomSectionReader;
+use wasmparser::Payload::CustomSection;
 use crate::errors::*;
+use crate::Module;
+use crate::dictionary::{dictionary, WASM_DICTIONARY_ID};
+use crate::optimize::Optimize;
+use crate::header::{write_header, Header, DELTA_FORMAT_VERSION, FLAG_DEDUPLICATED,
+                    FLAG_STRIPPED_ALL, FLAG_STRIPPED_DEBUG, FLAG_STRIPPED_UNUSED, FORMAT_VERSION};
 
-/// Compress file at `source`into a new file at `destination`
+/// The compression level used when none is specified
+pub const DEFAULT_LEVEL: u8 = 6;
+
+/// The highest compression level
+pub const MAX_LEVEL: u8 = 9;
+
+// zstd compression level used for each of the compression levels 0 to [MAX_LEVEL]
+const ZSTD_LEVELS: [i32; MAX_LEVEL as usize + 1] = [1, 3, 5, 7, 10, 15, 19, 20, 21, 22];
+
+/// Which custom sections to strip from a module when compressing it
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Strip {
+    /// Keep all custom sections
+    Nothing,
+    /// Strip the custom sections that hold debug information, including the name section
+    Debug,
+    /// Strip all custom sections
+    All,
+}
+
+impl Strip {
+    /// Return true if a custom section called `name` should be stripped
+    pub fn strips(&self, name: &str) -> bool {
+        match self {
+            Strip::Nothing => false,
+            Strip::Debug => name.starts_with(".debug_") || name == "name" ||
+                name == "sourceMappingURL" || name == "external_debug_info",
+            Strip::All => true,
+        }
+    }
+}
+
+/// Find the names of the custom sections in `module` that will be stripped by `strip`
+pub fn stripped_sections(module: &Module, strip: Strip) -> Vec<String> {
+    module.payloads.iter()
+        .filter_map(|payload| match payload {
+            CustomSection(reader) if strip.strips(reader.name()) => Some(reader.name().to_owned()),
+            _ => None,
+        })
+        .collect()
+}
+
+// Append a section to the output as its id, followed by the LEB128 encoded length of the
+// compressed contents and then the compressed contents themselves
+fn write_section(output: &mut Vec<u8>, id: u8, contents: &[u8], dictionary: &[u8],
+                 zstd_level: i32) -> Result<()> {
+    let mut encoder = if dictionary.is_empty() {
+        Encoder::new(Vec::new(), zstd_level)?
+    } else {
+        Encoder::with_dictionary(Vec::new(), zstd_level, dictionary)?
+    };
+    encoder.write_all(contents)?;
+    let compressed = encoder.finish()?;
+
+    output.push(id);
+    leb128::write::unsigned(output, compressed.len() as u64)?;
+    output.extend_from_slice(&compressed);
+    Ok(())
+}
+
+// Map the id and contents of each section of the `reference` module to its position in the
+// module, keeping the first of any identical sections
+fn sections_by_contents(reference: &[u8]) -> Result<HashMap<(u8, &[u8]), usize>> {
+    let mut sections = HashMap::new();
+    for (index, (id, range)) in Module::from_bytes(reference)?.sections()?.into_iter()
+        .enumerate() {
+        sections.entry((id, &reference[range])).or_insert(index);
+    }
+    Ok(sections)
+}
+
+/// Parse a compression level from either a number between 0 and [MAX_LEVEL] or one of the names
+/// "fast", "default" or "best"
+pub fn parse_level(level: &str) -> Result<u8> {
+    match level {
+        "fast" => Ok(0),
+        "default" => Ok(DEFAULT_LEVEL),
+        "best" => Ok(MAX_LEVEL),
+        _ => match level.parse::<u8>() {
+            Ok(number) if number <= MAX_LEVEL => Ok(number),
+            _ => Err(Error::InvalidLevel(level.to_owned())),
+        }
+    }
+}
+
+/// Compress file at `source`into a new file at `destination` using [compress_bytes]
 /// Return a Result with the size of the output file in bytes
-pub fn compress(source: &Path, destination: &Path) -> Result<u64> {
-    // TODO generate our compressed format
-    std::fs::copy(source, destination).chain_err(|| "Could not compress")
-}
\ No newline at end of file
+pub fn compress(source: &Path, destination: &Path, level: u8, strip: Strip, optimize: Optimize)
+    -> Result<u64> {
+    let buf = std::fs::read(source)?;
+    let output = compress_bytes(&buf, level, strip, optimize)?;
+    std::fs::write(destination, &output).chain_err(|| "Could not compress")?;
+    Ok(output.len() as u64)
+}
+
+/// Compress the WASM module in `input` using compression `level`, omitting the custom
+/// sections selected by `strip`. The module is first rewritten with the optimizations
+/// selected by `optimize`. A module that is not valid with [crate::parse::features] is
+/// rejected, as it could not be decompressed
+/// Return a Result with the compressed bytes
+pub fn compress_bytes(input: &[u8], level: u8, strip: Strip, optimize: Optimize)
+    -> Result<Vec<u8>> {
+    compress_bytes_with_reference(input, None, level, strip, optimize)
+}
+
+/// Compress the WASM module in `input` as [compress_bytes] does. If there is a `reference`
+/// module then only the sections that differ from those in it are stored, and the same
+/// reference is needed to decompress it
+/// Return a Result with the compressed bytes
+pub fn compress_bytes_with_reference(input: &[u8], reference: Option<&[u8]>, level: u8,
+                                     strip: Strip, optimize: Optimize) -> Result<Vec<u8>> {
+    let zstd_level = match ZSTD_LEVELS.get(level as usize) {
+        Some(zstd_level) => *zstd_level,
+        None => return Err(Error::InvalidLevel(level.to_string())),
+    };
+
+    let optimized;
+    let buf = if !optimize.is_none() {
+        optimized = optimize.apply(input)?;
+        &optimized
+    } else {
+        input
+    };
+    let module = Module::from_bytes(buf)?;
+    let module_sections = module.sections()?;
+    // reject a module that decompressing would not accept, as it validates the same way
+    crate::parse::validate(buf)?;
+    let dictionary_id = WASM_DICTIONARY_ID;
+    let dictionary = dictionary(dictionary_id)?;
+    let reference_sections = match reference {
+        Some(reference) => sections_by_contents(reference)?,
+        None => HashMap::new(),
+    };
+
+    // The hash is of the module that decompressing will reproduce, which is not the same as
+    // the original file when sections have been stripped or rewritten
+    let mut decompressed = wasm_encoder::Module::new();
+    let mut sections = vec![];
+    for (id, range) in module_sections {
+        if id == 0 {
+            let custom = CustomSectionReader::new(&buf[range.clone()], range.start)?;
+            if strip.strips(custom.name()) {
+                continue;
+            }
+        }
+        let contents = &buf[range];
+        decompressed.section(&wasm_encoder::RawSection { id, data: contents });
+        match reference_sections.get(&(id, contents)) {
+            Some(index) => {
+                sections.extend_from_slice(&[id, 0]);
+                leb128::write::unsigned(&mut sections, *index as u64)?;
+            },
+            None => write_section(&mut sections, id, contents, &dictionary, zstd_level)?,
+        }
+    }
+
+    let decompressed = decompressed.finish();
+    let mut flags = level;
+    match strip {
+        Strip::Nothing => {},
+        Strip::Debug => flags |= FLAG_STRIPPED_DEBUG,
+        Strip::All => flags |= FLAG_STRIPPED_ALL,
+    }
+    if optimize.deduplicate_types {
+        flags |= FLAG_DEDUPLICATED;
+    }
+    if optimize.strip_unused {
+        flags |= FLAG_STRIPPED_UNUSED;
+    }
+    let header = Header {
+        version: if reference.is_some() { DELTA_FORMAT_VERSION } else { FORMAT_VERSION },
+        flags,
+        dictionary_id,
+        length: decompressed.len() as u64,
+        hash: Sha256::digest(&decompressed).into(),
+    };
+
+    let mut output = vec![];
+    write_header(&mut output, &header);
+    if let Some(reference) = reference {
+        output.extend_from_slice(&Sha256::digest(reference));
+    }
+    output.extend_from_slice(&sections);
+
+    Ok(output)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{compress_bytes, parse_level, Strip, DEFAULT_LEVEL, MAX_LEVEL};
+    use crate::errors::Error;
+    use crate::optimize::Optimize;
+
+    #[test]
+    fn test_strips() {
+        assert!(!Strip::Nothing.strips("name"));
+        assert!(Strip::Debug.strips("name"));
+        assert!(Strip::Debug.strips(".debug_info"));
+        assert!(!Strip::Debug.strips("producers"));
+        assert!(Strip::All.strips("producers"));
+    }
+
+    #[test]
+    fn test_parse_level() {
+        assert_eq!(parse_level("0").expect("Could not parse level"), 0);
+        assert_eq!(parse_level("fast").expect("Could not parse level"), 0);
+        assert_eq!(parse_level("default").expect("Could not parse level"), DEFAULT_LEVEL);
+        assert_eq!(parse_level("best").expect("Could not parse level"), MAX_LEVEL);
+        assert!(matches!(parse_level("10"), Err(Error::InvalidLevel(level)) if level == "10"));
+        assert!(matches!(parse_level("-1"), Err(Error::InvalidLevel(_))));
+        assert!(matches!(parse_level("fastest"), Err(Error::InvalidLevel(_))));
+        assert!(matches!(compress_bytes(b"\0asm\x01\0\0\0", MAX_LEVEL + 1, Strip::Nothing,
+                                        Optimize::default()),
+                         Err(Error::InvalidLevel(_))));
+    }
+
+    #[test]
+    fn test_compress_invalid() {
+        // a function section that refers to a type that doesn't exist parses, but is invalid
+        let invalid = b"\0asm\x01\0\0\0\x03\x02\x01\x05";
+        assert!(matches!(compress_bytes(invalid, DEFAULT_LEVEL, Strip::Nothing,
+                                        Optimize::default()),
+                         Err(Error::Parse { .. })));
+
+        // a module using proposals that are not enabled by default can be compressed
+        let memory64 = crate::parse::wat_to_wasm("(module (memory i64 1))")
+            .expect("Could not compile memory64 module");
+        let compressed = compress_bytes(&memory64, DEFAULT_LEVEL, Strip::Nothing,
+                                        Optimize::default())
+            .expect("Could not compress memory64 module");
+        assert_eq!(crate::decompress_bytes(&compressed, true).expect("Could not decompress"),
+                   memory64);
+    }
+}
@@ -1,56 +1,389 @@
 use std::fs;
-use std::io::Write;
+use std::io::Read;
 use std::path::Path;
 use crate::errors::*;
-use wasm_encoder::{
-    CodeSection, ExportKind, ExportSection, Function, FunctionSection, Instruction,
-    Module, TypeSection, ValType,
-};
+use crate::compress::Strip;
+use crate::optimize::Optimize;
+use crate::header::{read_header, DELTA_FORMAT_VERSION, HASH_LENGTH};
+use sha2::{Digest, Sha256};
+use crate::dictionary::dictionary;
+use zstd::stream::Decoder;
+use wasm_encoder::{Module, RawSection};
 
-/// Decompress file at `source`into a new file at `destination`
+/// Decompress file at `source`into a new file at `destination` using [decompress_bytes]
 /// Return a Result with the size of the output file in bytes
-pub fn decompress(_source: &Path, destination: &Path) -> Result<u64> {
-    // TODO parse our compressed format
+pub fn decompress(source: &Path, destination: &Path, check_hash: bool) -> Result<u64> {
+    let wasm_bytes = decompress_bytes(&fs::read(source)?, check_hash)?;
+
+    let mut file = fs::File::create(destination)?;
+    std::io::Write::write_all(&mut file, &wasm_bytes)?;
+
+    Ok(wasm_bytes.len() as u64)
+}
+
+/// Verify that the compressed file at `compressed` decompresses to a module equivalent to
+/// the one at `original`, once the custom sections selected by `strip` are ignored and
+/// `original` is optimized as selected by `optimize`. The `reference` module is needed if
+/// `compressed` was compressed against one
+pub fn verify(original: &Path, compressed: &Path, strip: Strip, optimize: Optimize,
+              reference: Option<&[u8]>) -> Result<()> {
+    let mut original_buf = fs::read(original)?;
+    if !optimize.is_none() {
+        original_buf = optimize.apply(&original_buf)?;
+    }
+    let original_module = crate::Module::from_bytes(&original_buf)?;
+    let decompressed_buf = decompress_bytes_with_reference(&fs::read(compressed)?, reference,
+                                                           true)?;
+    let decompressed_module = crate::Module::from_bytes(&decompressed_buf)?;
+
+    let mut original_sections = vec![];
+    for (id, range) in original_module.sections()? {
+        if id == 0 {
+            let custom = wasmparser::CustomSectionReader::new(&original_buf[range.clone()],
+                                                              range.start)?;
+            if strip.strips(custom.name()) {
+                continue;
+            }
+        }
+        original_sections.push((id, &original_buf[range]));
+    }
+    let decompressed_sections: Vec<(u8, &[u8])> = decompressed_module.sections()?
+        .into_iter()
+        .map(|(id, range)| (id, &decompressed_buf[range]))
+        .collect();
+
+    for (index, (original_section, decompressed_section)) in
+        original_sections.iter().zip(decompressed_sections.iter()).enumerate() {
+        if original_section != decompressed_section {
+            return Err(Error::VerificationFailed(format!(
+                "Section #{} (id {}) differs after decompressing '{}'", index,
+                original_section.0, compressed.display())));
+        }
+    }
+    if original_sections.len() != decompressed_sections.len() {
+        return Err(Error::VerificationFailed(format!(
+            "'{}' has {} sections, but decompressing '{}' produced {}", original.display(),
+            original_sections.len(), compressed.display(), decompressed_sections.len())));
+    }
+
+    Ok(())
+}
+
+/// Decompress the ".wz" contents in `input`. If `check_hash` is true then the decompressed
+/// module must match the hash stored when it was compressed
+/// Return a Result with the bytes of the validated WASM module
+pub fn decompress_bytes(input: &[u8], check_hash: bool) -> Result<Vec<u8>> {
+    decompress_bytes_with_reference(input, None, check_hash)
+}
+
+/// Decompress the ".wz" contents in `input` as [decompress_bytes] does. If it was compressed
+/// against a reference module then `reference` must be that same module
+/// Return a Result with the bytes of the validated WASM module
+pub fn decompress_bytes_with_reference(input: &[u8], reference: Option<&[u8]>,
+                                       check_hash: bool) -> Result<Vec<u8>> {
+    let (header, mut reader) = read_header(input)?;
+    let dictionary = dictionary(header.dictionary_id)?;
+
+    let delta = header.version == DELTA_FORMAT_VERSION;
+    let mut reference_sections = vec![];
+    if delta {
+        if reader.len() < HASH_LENGTH {
+            return Err(Error::TruncatedHeader);
+        }
+        let (hash, rest) = reader.split_at(HASH_LENGTH);
+        reader = rest;
+        let reference = reference.ok_or(Error::ReferenceMissing)?;
+        if Sha256::digest(reference).as_slice() != hash {
+            return Err(Error::ReferenceMismatch);
+        }
+        for (id, range) in crate::Module::from_bytes(reference)?.sections()? {
+            reference_sections.push((id, &reference[range]));
+        }
+    }
 
     let mut module = Module::new();
+    while let Some((&id, rest)) = reader.split_first() {
+        reader = rest;
+        let length = leb128::read::unsigned(&mut reader)
+            .map_err(|_| Error::TruncatedSection { id })? as usize;
+        if delta && length == 0 {
+            let index = leb128::read::unsigned(&mut reader)
+                .map_err(|_| Error::TruncatedSection { id })? as usize;
+            match reference_sections.get(index) {
+                Some(&(reference_id, contents)) if reference_id == id =>
+                    module.section(&RawSection { id, data: contents }),
+                _ => return Err(Error::ReferenceSectionMissing { index, id }),
+            };
+            continue;
+        }
+        if length > reader.len() {
+            return Err(Error::TruncatedSection { id });
+        }
+        let (compressed, rest) = reader.split_at(length);
+        let mut contents = vec![];
+        if dictionary.is_empty() {
+            Decoder::new(compressed)?.read_to_end(&mut contents)?;
+        } else {
+            Decoder::with_dictionary(compressed, &dictionary)?.read_to_end(&mut contents)?;
+        }
+        module.section(&RawSection { id, data: &contents });
+        reader = rest;
+    }
 
-    // Encode the type section.
-    let mut types = TypeSection::new();
-    let params = vec![ValType::I32, ValType::I32];
-    let results = vec![ValType::I32];
-    types.function(params, results);
-    module.section(&types);
-
-    // Encode the function section.
-    let mut functions = FunctionSection::new();
-    let type_index = 0;
-    functions.function(type_index);
-    module.section(&functions);
-
-    // Encode the export section.
-    let mut exports = ExportSection::new();
-    exports.export("f", ExportKind::Func, 0);
-    module.section(&exports);
-
-    // Encode the code section.
-    let mut codes = CodeSection::new();
-    let locals = vec![];
-    let mut f = Function::new(locals);
-    f.instruction(&Instruction::LocalGet(0));
-    f.instruction(&Instruction::LocalGet(1));
-    f.instruction(&Instruction::I32Add);
-    f.instruction(&Instruction::End);
-    codes.function(&f);
-    module.section(&codes);
-
-    // Extract the encoded Wasm bytes for this module.
+    // Extract the encoded Wasm bytes for this module and check they are valid
     let wasm_bytes = module.finish();
+    if wasm_bytes.len() as u64 != header.length {
+        return Err(Error::LengthMismatch { actual: wasm_bytes.len() as u64,
+                                           expected: header.length });
+    }
+    if check_hash && Sha256::digest(&wasm_bytes).as_slice() != header.hash {
+        return Err(Error::ChecksumMismatch);
+    }
+    crate::parse::validate(&wasm_bytes)?;
+
+    Ok(wasm_bytes)
+}
 
-    // We generated a valid Wasm module!
-    assert!(wasmparser::validate(&wasm_bytes).is_ok());
+#[cfg(test)]
+mod test {
+    use std::fs;
+    use std::path::PathBuf;
+    use wax::Glob;
+    use crate::Module;
+    use crate::parse::ModuleKind;
+    use crate::compress::{Strip, DEFAULT_LEVEL};
+    use crate::optimize::Optimize;
+    use super::{decompress_bytes_with_reference, verify};
+    use crate::compress::compress_bytes_with_reference;
+    use crate::header::{HASH_LENGTH, HEADER_LENGTH};
+    use crate::errors::Error;
+    use wasmparser::Payload::CustomSection;
 
-    let mut file = fs::File::create(destination)?;
-    file.write_all(&wasm_bytes)?;
+    #[test]
+    fn test_round_trip() {
+        let test_files_dir = PathBuf::from(&format!("{}/tests/test_files", env!("CARGO_MANIFEST_DIR")));
+        let glob = Glob::new("*.{wasm,wat}").expect("Globbing error");
+        for entry in glob.walk(test_files_dir) {
+            let entry = entry.expect("Could not read test file");
+            let file_name = entry.path().file_name().expect("No file name").to_string_lossy();
+            let compressed = std::env::temp_dir()
+                .join(format!("{}_{}.wz", file_name, std::process::id()));
+            let decompressed = compressed.with_extension("");
 
-    Ok(wasm_bytes.len() as u64)
+            // compile the text files, so that every sample module is round tripped
+            let source = compressed.with_extension("wasm");
+            let original_buf = match entry.path().extension() {
+                Some(extension) if extension == "wat" => {
+                    let text = fs::read_to_string(entry.path()).expect("Could not read original");
+                    crate::parse::wat_to_wasm(&text).expect("Could not compile original")
+                }
+                _ => fs::read(entry.path()).expect("Could not read original"),
+            };
+            fs::write(&source, &original_buf).expect("Could not write original");
+
+            let original = Module::from_source(&source, &original_buf)
+                .expect("Could not parse original");
+            let result = crate::compress(&source, &compressed, DEFAULT_LEVEL, Strip::Nothing,
+                                         Optimize::default());
+            if original.kind == ModuleKind::Component {
+                assert!(matches!(result, Err(Error::UnsupportedComponent(_))),
+                        "Compressed component {}", file_name);
+                let _ = fs::remove_file(&source);
+                continue;
+            }
+            result.unwrap_or_else(|e| panic!("Could not compress {}: {}", file_name, e));
+            crate::decompress(&compressed, &decompressed, true)
+                .unwrap_or_else(|e| panic!("Could not decompress {}: {}", file_name, e));
+
+            let round_trip_buf = fs::read(&decompressed).expect("Could not read decompressed");
+            let round_trip = Module::from_source(&decompressed, &round_trip_buf)
+                .expect("Could not parse decompressed");
+
+            let original_sections = original.sections().expect("No sections");
+            let round_trip_sections = round_trip.sections().expect("No sections");
+            assert_eq!(original_sections.len(), round_trip_sections.len());
+            for ((id, range), (round_trip_id, round_trip_range)) in
+                original_sections.into_iter().zip(round_trip_sections) {
+                assert_eq!(id, round_trip_id);
+                assert_eq!(original_buf[range], round_trip_buf[round_trip_range]);
+            }
+
+            let _ = fs::remove_file(&source);
+            let _ = fs::remove_file(&compressed);
+            let _ = fs::remove_file(&decompressed);
+        }
+    }
+
+    // Write a copy of a test file with the custom sections `names` appended, returning its path
+    fn with_custom_sections(prefix: &str, names: &[&str]) -> PathBuf {
+        let source = PathBuf::from(&format!("{}/tests/test_files/compare_switch_stripped.wasm",
+                                            env!("CARGO_MANIFEST_DIR")));
+        let mut buf = fs::read(source).expect("Could not read test file");
+        for name in names {
+            buf.push(0);
+            wasm_encoder::Encode::encode(&wasm_encoder::CustomSection {
+                name: (*name).into(),
+                data: vec!(1, 2, 3).into(),
+            }, &mut buf);
+        }
+        let path = std::env::temp_dir()
+            .join(format!("{}_{}.wasm", prefix, std::process::id()));
+        fs::write(&path, &buf).expect("Could not write test file");
+        path
+    }
+
+    #[test]
+    fn test_strip() {
+        let with_custom = with_custom_sections("strip", &[".debug_info", "producers"]);
+        let compressed = with_custom.with_extension("wasm.wz");
+
+        crate::compress(&with_custom, &compressed, DEFAULT_LEVEL, Strip::Debug, Optimize::default())
+            .expect("Could not compress");
+        crate::decompress(&compressed, &with_custom, true).expect("Could not decompress");
+
+        let round_trip_buf = fs::read(&with_custom).expect("Could not read decompressed");
+        let round_trip = Module::from_source(&with_custom, &round_trip_buf)
+            .expect("Could not parse decompressed");
+        let custom_names: Vec<&str> = round_trip.payloads.iter()
+            .filter_map(|payload| match payload {
+                CustomSection(reader) => Some(reader.name()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(custom_names, vec!("producers"));
+
+        let _ = fs::remove_file(&with_custom);
+        let _ = fs::remove_file(&compressed);
+    }
+
+    #[test]
+    fn test_verify() {
+        let source = with_custom_sections("verify", &["producers"]);
+        let compressed = source.with_extension("wasm.wz");
+
+        crate::compress(&source, &compressed, DEFAULT_LEVEL, Strip::Nothing, Optimize::default())
+            .expect("Could not compress");
+        verify(&source, &compressed, Strip::Nothing, Optimize::default(), None)
+            .expect("Verification failed");
+
+        crate::compress(&source, &compressed, DEFAULT_LEVEL, Strip::All, Optimize::default())
+            .expect("Could not compress");
+        assert!(matches!(verify(&source, &compressed, Strip::Nothing, Optimize::default(), None),
+                         Err(Error::VerificationFailed(_))));
+        verify(&source, &compressed, Strip::All, Optimize::default(), None)
+            .expect("Verification failed");
+
+        let _ = fs::remove_file(&source);
+        let _ = fs::remove_file(&compressed);
+    }
+
+    #[test]
+    fn test_hash_mismatch() {
+        let source = with_custom_sections("hash", &[]);
+        let compressed = source.with_extension("wasm.wz");
+        crate::compress(&source, &compressed, DEFAULT_LEVEL, Strip::Nothing, Optimize::default())
+            .expect("Could not compress");
+
+        // corrupt the stored hash, leaving the compressed sections intact
+        let mut wz = fs::read(&compressed).expect("Could not read compressed");
+        wz[HEADER_LENGTH - 1] ^= 0xff;
+        fs::write(&compressed, &wz).expect("Could not write corrupted file");
+
+        match crate::decompress(&compressed, &source, true) {
+            Err(Error::ChecksumMismatch) => {},
+            other => panic!("Expected a ChecksumMismatch error, got: {:?}", other),
+        }
+        crate::decompress(&compressed, &source, false).expect("Could not decompress");
+
+        let _ = fs::remove_file(&source);
+        let _ = fs::remove_file(&compressed);
+    }
+
+    #[test]
+    fn test_corrupt_wazm_file() {
+        let source = PathBuf::from(&format!("{}/tests/test_files/compare_switch_stripped.wasm",
+                                            env!("CARGO_MANIFEST_DIR")));
+        let original = fs::read(source).expect("Could not read test file");
+        let wz = crate::compress_bytes(&original, DEFAULT_LEVEL, Strip::Nothing,
+                                       Optimize::default())
+            .expect("Could not compress");
+
+        let mut wrong_length = wz.clone();
+        wrong_length[7] ^= 0x01;
+        assert!(matches!(crate::decompress_bytes(&wrong_length, true),
+                         Err(Error::LengthMismatch { expected, .. })
+                            if expected != original.len() as u64));
+
+        let mut unknown_dictionary = wz.clone();
+        unknown_dictionary[6] = 0x77;
+        assert!(matches!(crate::decompress_bytes(&unknown_dictionary, true),
+                         Err(Error::UnknownDictionary(0x77))));
+
+        assert!(matches!(crate::decompress_bytes(&wz[..wz.len() - 1], true),
+                         Err(Error::TruncatedSection { id: 11 })));
+        assert!(matches!(crate::decompress_bytes(&wz[..HEADER_LENGTH - 1], true),
+                         Err(Error::TruncatedHeader)));
+    }
+
+    #[test]
+    fn test_reference_section_missing() {
+        let source = PathBuf::from(&format!("{}/tests/test_files/compare_switch_stripped.wasm",
+                                            env!("CARGO_MANIFEST_DIR")));
+        let reference = fs::read(source).expect("Could not read test file");
+        let mut delta = compress_bytes_with_reference(&reference, Some(&reference), DEFAULT_LEVEL,
+                                                      Strip::Nothing, Optimize::default())
+            .expect("Could not compress against reference");
+
+        // every section is unchanged, so the first one is its id, a zero length and position 0
+        let first_section = HEADER_LENGTH + HASH_LENGTH;
+        assert_eq!(delta[first_section + 1..first_section + 3], [0, 0]);
+        delta[first_section + 2] = 0x7f;
+        assert!(matches!(decompress_bytes_with_reference(&delta, Some(&reference), true),
+                         Err(Error::ReferenceSectionMissing { index: 0x7f, .. })));
+
+        assert!(matches!(decompress_bytes_with_reference(&delta[..first_section - 1],
+                                                         Some(&reference), true),
+                         Err(Error::TruncatedHeader)));
+    }
+
+    #[test]
+    fn test_round_trip_bytes() {
+        let source = PathBuf::from(&format!("{}/tests/test_files/compare_switch_stripped.wasm",
+                                            env!("CARGO_MANIFEST_DIR")));
+        let original = fs::read(source).expect("Could not read test file");
+        let compressed = crate::compress_bytes(&original, DEFAULT_LEVEL, Strip::Nothing,
+                                               Optimize::default())
+            .expect("Could not compress");
+        assert!(compressed.len() < original.len());
+        let decompressed = crate::decompress_bytes(&compressed, true)
+            .expect("Could not decompress");
+        assert_eq!(decompressed, original);
+    }
+
+    #[test]
+    fn test_reference_round_trip() {
+        let source = PathBuf::from(&format!("{}/tests/test_files/compare_switch_stripped.wasm",
+                                            env!("CARGO_MANIFEST_DIR")));
+        let reference = fs::read(source).expect("Could not read test file");
+        let changed_path = with_custom_sections("reference", &["producers"]);
+        let changed = fs::read(&changed_path).expect("Could not read changed file");
+
+        let standalone = crate::compress_bytes(&changed, DEFAULT_LEVEL, Strip::Nothing,
+                                               Optimize::default())
+            .expect("Could not compress");
+        let delta = compress_bytes_with_reference(&changed, Some(&reference), DEFAULT_LEVEL,
+                                                  Strip::Nothing, Optimize::default())
+            .expect("Could not compress against reference");
+        assert!(delta.len() < standalone.len());
+
+        let decompressed = decompress_bytes_with_reference(&delta, Some(&reference), true)
+            .expect("Could not decompress against reference");
+        assert_eq!(decompressed, changed);
+
+        assert!(matches!(crate::decompress_bytes(&delta, true), Err(Error::ReferenceMissing)));
+        assert!(matches!(decompress_bytes_with_reference(&delta, Some(&changed), true),
+                         Err(Error::ReferenceMismatch)));
+
+        let _ = fs::remove_file(&changed_path);
+    }
 }
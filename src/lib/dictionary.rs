@@ -0,0 +1,62 @@
+use crate::errors::*;
+
+/// Id written in the ".wz" header when sections are compressed without a dictionary
+pub const NO_DICTIONARY: u8 = 0;
+
+/// Id written in the ".wz" header when sections are compressed with [WASM_DICTIONARY]
+pub const WASM_DICTIONARY_ID: u8 = 1;
+
+/// A raw content dictionary of byte sequences that are common in wasm modules: the names of
+/// frequently used imports, exports and custom sections, followed by common sequences of
+/// instructions and LEB128 encoded immediates. zstd favours content at the end of a raw
+/// dictionary so the most common sequences are placed last.
+pub const WASM_DICTIONARY: &[u8] = concat!(
+    // Custom section names and their common contents
+    "producers", "language", "processed-by", "sdk", "Rust", "C11", "clang", "rustc",
+    "walrus", "wasm-bindgen", "wasm-opt", "target_features", "+mutable-globals",
+    "+sign-ext", "+bulk-memory", "+nontrapping-fptoint", ".debug_info", ".debug_line",
+    ".debug_abbrev", ".debug_str", ".debug_ranges", "sourceMappingURL", "name",
+    // Common import modules and names
+    "wasi_snapshot_preview1", "fd_write", "fd_read", "fd_close", "fd_seek", "proc_exit",
+    "environ_get", "environ_sizes_get", "args_get", "args_sizes_get", "clock_time_get",
+    "random_get", "__wbindgen_placeholder__", "__wbindgen_externref_xform__",
+    "__wbindgen_describe", "__wbindgen_throw", "__wbindgen_malloc", "__wbindgen_realloc",
+    "__wbindgen_free", "__wbindgen_exn_store", "console", "log", "env", "js",
+    // Common exports and globals
+    "__indirect_function_table", "__stack_pointer", "__data_end", "__heap_base",
+    "__dso_handle", "_initialize", "_start", "main", "memory",
+).as_bytes();
+
+/// Sequences of instruction bytes that are common in function bodies produced by compilers
+pub const WASM_CODE_DICTIONARY: &[u8] = &[
+    // block / loop / if with empty block type, br_if 0, br 0, br 1, return, unreachable
+    0x02, 0x40, 0x03, 0x40, 0x04, 0x40, 0x0d, 0x00, 0x0c, 0x00, 0x0c, 0x01, 0x0f, 0x00,
+    // i32.load and i32.store with natural alignment at offsets 0, 4 and 8
+    0x28, 0x02, 0x00, 0x28, 0x02, 0x04, 0x28, 0x02, 0x08,
+    0x36, 0x02, 0x00, 0x36, 0x02, 0x04, 0x36, 0x02, 0x08,
+    // i64.load and i64.store at offsets 0 and 8
+    0x29, 0x03, 0x00, 0x29, 0x03, 0x08, 0x37, 0x03, 0x00, 0x37, 0x03, 0x08,
+    // i32.load8_u, i32.store8
+    0x2d, 0x00, 0x00, 0x3a, 0x00, 0x00,
+    // stack pointer prologue: global.get 0, i32.const 16, i32.sub, local.tee, global.set 0
+    0x23, 0x00, 0x41, 0x10, 0x6b, 0x22, 0x01, 0x24, 0x00,
+    0x23, 0x00, 0x41, 0x20, 0x6b, 0x22, 0x02, 0x24, 0x00,
+    // stack pointer epilogue: local.get, i32.const 16, i32.add, global.set 0
+    0x20, 0x01, 0x41, 0x10, 0x6a, 0x24, 0x00,
+    // i32.const 0, 1, -1 followed by comparisons and arithmetic
+    0x41, 0x00, 0x41, 0x01, 0x41, 0x7f, 0x46, 0x45, 0x47, 0x6a, 0x6b, 0x71, 0x72,
+    // local.get / local.set / local.tee of the first locals
+    0x21, 0x00, 0x21, 0x01, 0x21, 0x02, 0x21, 0x03, 0x22, 0x00, 0x22, 0x01, 0x22, 0x02,
+    0x20, 0x03, 0x20, 0x02, 0x20, 0x01, 0x20, 0x00,
+    // call, end, end of function
+    0x10, 0x00, 0x0b, 0x0b,
+];
+
+/// Get the dictionary that was used to compress sections written with dictionary `id`
+pub fn dictionary(id: u8) -> Result<Vec<u8>> {
+    match id {
+        NO_DICTIONARY => Ok(vec![]),
+        WASM_DICTIONARY_ID => Ok([WASM_DICTIONARY, WASM_CODE_DICTIONARY].concat()),
+        _ => Err(Error::UnknownDictionary(id)),
+    }
+}
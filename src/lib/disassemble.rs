@@ -0,0 +1,99 @@
+use std::fmt::Write;
+use wasmparser::{FunctionBody, Operator, TypeRef};
+use wasmparser::Payload::{CodeSectionEntry, ImportSection};
+use crate::errors::*;
+use crate::Module;
+
+/// Disassemble the body of the function at `function_index` in `module` into readable text,
+/// with one operator per line prefixed by its offset and indented by the depth of the blocks
+/// it is nested in. The output is not valid WAT
+pub fn disassemble(module: &Module, function_index: usize) -> Result<String> {
+    let mut imported_function_count = 0;
+    let mut body_index = 0;
+    for payload in &module.payloads {
+        match payload {
+            ImportSection(reader) => {
+                for import in reader.clone() {
+                    if let TypeRef::Func(_) = import?.ty {
+                        imported_function_count += 1;
+                    }
+                }
+                if function_index < imported_function_count {
+                    return Err(Error::ImportedFunction(function_index));
+                }
+            },
+            CodeSectionEntry(body) => {
+                if imported_function_count + body_index == function_index {
+                    return disassemble_body(body);
+                }
+                body_index += 1;
+            },
+            _ => {},
+        }
+    }
+
+    Err(Error::FunctionNotFound { function: function_index.to_string(),
+                                  function_count: imported_function_count + body_index })
+}
+
+// Write each operator of a function body on its own line
+fn disassemble_body(body: &FunctionBody) -> Result<String> {
+    let mut output = String::new();
+    for locals in body.get_locals_reader()? {
+        let (count, val_type) = locals?;
+        let _ = writeln!(output, "{:12}local {} x {}", "", count, val_type);
+    }
+
+    // the body itself is closed by a final End, so starts one level deep
+    let mut depth = 1;
+    let mut reader = body.get_operators_reader()?;
+    while !reader.eof() {
+        let offset = reader.original_position();
+        let operator = reader.read()?;
+        let (before, after) = match operator {
+            Operator::Block { .. } | Operator::Loop { .. } | Operator::If { .. } |
+            Operator::Try { .. } => (0, 1),
+            Operator::Else | Operator::Catch { .. } | Operator::CatchAll => (-1, 1),
+            Operator::End | Operator::Delegate { .. } => (-1, 0),
+            _ => (0, 0),
+        };
+        depth = (depth + before).max(0);
+        // the debug format of an operator is its name followed by any immediate values
+        let _ = writeln!(output, "{:#010x}  {}{:?}", offset, "  ".repeat(depth as usize),
+                         operator);
+        depth += after;
+    }
+
+    Ok(output)
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+    use std::path::PathBuf;
+    use crate::Module;
+    use crate::errors::Error;
+    use crate::parse::wat_to_wasm;
+    use super::disassemble;
+
+    #[test]
+    fn test_disassemble() {
+        let source = PathBuf::from(&format!("{}/tests/test_files/control_flow.wat",
+                                            env!("CARGO_MANIFEST_DIR")));
+        let wat = fs::read_to_string(source).expect("Could not read test file");
+        let buf = wat_to_wasm(&wat).expect("Could not compile test file");
+        let module = Module::from_bytes(&buf).expect("Could not parse test wasm");
+
+        let text = disassemble(&module, 0).expect("Could not disassemble function");
+        let lines: Vec<&str> = text.lines().collect();
+        assert!(lines[0].starts_with("0x"));
+        let last = lines.last().expect("No operators disassembled");
+        assert!(last.ends_with("  End"));
+        // the End closing the function body is indented the least
+        let indent = |line: &str| line[10..].len() - line[10..].trim_start().len();
+        assert!(lines.iter().all(|line| indent(line) >= indent(last)));
+        assert!(lines.iter().any(|line| indent(line) > indent(last)));
+
+        assert!(matches!(disassemble(&module, 100), Err(Error::FunctionNotFound { .. })));
+    }
+}
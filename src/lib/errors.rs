@@ -1,15 +1,162 @@
-#![allow(missing_docs)]
+use std::path::PathBuf;
+use thiserror::Error;
 
-pub use error_chain::bail;
-use error_chain::error_chain;
+/// The errors returned by the library, so that callers can match on the kind of error
+#[derive(Debug, Error)]
+pub enum Error {
+    /// Reading or writing a file failed
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
 
-error_chain! {
-    types {
-        Error, ErrorKind, ResultExt, Result;
+    /// The bytes of a WASM module or component could not be parsed
+    #[error("parse error at offset {offset:#x}: {message}")]
+    Parse { offset: usize, message: String },
+
+    /// The operation is only supported for core WASM modules, not components, with a message
+    /// saying what isn't supported
+    #[error("{0}")]
+    UnsupportedComponent(String),
+
+    /// The version in the header of a WASM module is not valid
+    #[error("Invalid WASM version in module")]
+    InvalidVersion,
+
+    /// A WASM module ended part way through a payload, after this many bytes of complete
+    /// payloads were read
+    #[error("parse error at offset {0:#x}: WASM module is truncated")]
+    Truncated(usize),
+
+    /// WebAssembly text could not be compiled, at this 1-based line and column of the text
+    #[error("WAT error at line {line}, column {column}: {message}")]
+    Wat { line: usize, column: usize, message: String },
+
+    /// A WASM module does not start with a header giving its version
+    #[error("WASM module has no header")]
+    MissingHeader,
+
+    /// A section type is not one of the names in [crate::analysis::SECTION_TYPES]
+    #[error("Unknown section type '{0}', valid types are: {types}",
+            types = crate::analysis::SECTION_TYPES.join(", "))]
+    UnknownSectionType(String),
+
+    /// No function has the index or name given, in a module with this many functions
+    #[error("No function found matching '{function}', the module has {function_count} functions")]
+    FunctionNotFound { function: String, function_count: usize },
+
+    /// The function with this index is imported, so has no body in the module
+    #[error("Function {0} is imported so has no body")]
+    ImportedFunction(usize),
+
+    /// A module uses array types from the GC proposal, which can't be deduplicated
+    #[error("Cannot deduplicate array types")]
+    UnsupportedArrayType,
+
+    /// Two files in a directory of split sections have the same section number
+    #[error("Both '{}' and '{}' are section number {number}", .first.display(), .second.display())]
+    DuplicateSectionFile { number: usize, first: PathBuf, second: PathBuf },
+
+    /// A directory of split sections has no section files
+    #[error("No section files found in '{}'", .0.display())]
+    NoSectionFiles(PathBuf),
+
+    /// A directory of split sections has no file for the section with this number
+    #[error("The file for section number {number} is missing from '{}'", .directory.display())]
+    MissingSectionFile { number: usize, directory: PathBuf },
+
+    /// A file does not start with the magic bytes of a ".wz" file
+    #[error("Not a wazm file")]
+    NotWazm,
+
+    /// A ".wz" file has a format version that is not known
+    #[error("Unsupported wazm format version {0}")]
+    UnsupportedFormatVersion(u8),
+
+    /// A ".wz" file ended part way through its header
+    #[error("Truncated wazm file header")]
+    TruncatedHeader,
+
+    /// A ".wz" file ended part way through the compressed section with this id
+    #[error("Truncated section with id {id} in wazm file")]
+    TruncatedSection { id: u8 },
+
+    /// A ".wz" file was compressed with a dictionary that is not known
+    #[error("Unknown compression dictionary id {0}")]
+    UnknownDictionary(u8),
+
+    /// A compression level is not a number from 0 to [crate::compress::MAX_LEVEL] or a name
+    #[error("Invalid compression level '{0}', expected 0-{max}, 'fast', 'default' or 'best'",
+            max = crate::compress::MAX_LEVEL)]
+    InvalidLevel(String),
+
+    /// A decompressed module is not the length stored when it was compressed
+    #[error("Decompressed module is {actual} bytes, but should be {expected} bytes")]
+    LengthMismatch { actual: u64, expected: u64 },
+
+    /// A decompressed module is not equivalent to the original, with a message saying how
+    #[error("{0}")]
+    VerificationFailed(String),
+
+    /// A decompressed module does not match the hash stored when it was compressed
+    #[error("Decompressed module does not match the hash in the wazm file")]
+    ChecksumMismatch,
+
+    /// A ".wz" file was compressed against a reference module, which was not given
+    #[error("The wazm file was compressed against a reference module, which is needed to \
+             decompress it")]
+    ReferenceMissing,
+
+    /// A ".wz" file refers to a section of its reference module that doesn't exist, by its
+    /// position and id
+    #[error("Reference module has no section #{index} with id {id}")]
+    ReferenceSectionMissing { index: usize, id: u8 },
+
+    /// The reference module given is not the one a ".wz" file was compressed against
+    #[error("The reference module does not match the hash of the one the wazm file was \
+             compressed against")]
+    ReferenceMismatch,
+
+    /// An error with a message describing what failed, caused by another error
+    #[error("{message}")]
+    Context {
+        message: String,
+        #[source]
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+
+    /// An internal invariant of the library did not hold, described by its message. Errors a
+    /// caller may need to handle have their own variant
+    #[error("{0}")]
+    Other(String),
+}
+
+impl From<wasmparser::BinaryReaderError> for Error {
+    fn from(e: wasmparser::BinaryReaderError) -> Self {
+        Error::Parse { offset: e.offset(), message: e.message().to_owned() }
     }
+}
+
+/// A Result with an [Error]
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Add a message describing what failed to the error of a [std::result::Result]
+pub trait ResultExt<T> {
+    /// Replace any error with an [Error::Context] with the message returned by `message`,
+    /// caused by the original error
+    fn chain_err<M: Into<String>, F: FnOnce() -> M>(self, message: F) -> Result<T>;
+}
 
-    foreign_links {
-        Io(std::io::Error);
-        Wasm(wasmparser::BinaryReaderError);
+impl<T, E: std::error::Error + Send + Sync + 'static> ResultExt<T> for std::result::Result<T, E> {
+    fn chain_err<M: Into<String>, F: FnOnce() -> M>(self, message: F) -> Result<T> {
+        self.map_err(|e| Error::Context { message: message().into(), source: Box::new(e) })
     }
 }
+
+/// Return early with an [Error::Other] with the message formatted from the arguments, for an
+/// internal invariant that did not hold
+macro_rules! bail {
+    ($($arg:tt)*) => {
+        return Err($crate::errors::Error::Other(format!($($arg)*)))
+    };
+}
+
+pub(crate) use bail;
@@ -0,0 +1,180 @@
+//! The header at the start of every ".wz" file.
+//!
+//! All multi-byte fields are little-endian, and the header is always [HEADER_LENGTH] bytes:
+//!
+//! | Offset | Size | Field                                                    |
+//! |--------|------|----------------------------------------------------------|
+//! | 0      | 4    | Magic bytes [MAGIC]                                      |
+//! | 4      | 1    | Format version, currently [FORMAT_VERSION]               |
+//! | 5      | 1    | Flags: compression level, strip and optimization flags   |
+//! | 6      | 1    | Id of the dictionary the sections were compressed with   |
+//! | 7      | 8    | Length in bytes of the decompressed module               |
+//! | 15     | 32   | SHA-256 hash of the decompressed module                  |
+//!
+//! The header is followed by the compressed sections, each one being the section id byte,
+//! the LEB128 encoded length of the compressed contents, then the compressed contents.
+//!
+//! Files compressed against a reference module have version [DELTA_FORMAT_VERSION]. Their
+//! header is followed by the SHA-256 hash of the reference module before the sections, and
+//! a section identical to one in the reference is stored as its id byte, a length of zero,
+//! then the LEB128 encoded position of the identical section in the reference module.
+//!
+//! Readers must reject files with a version they don't know, as any part of the format
+//! after the version may change between versions.
+use crate::errors::*;
+
+/// Magic bytes that start every ".wz" file
+pub const MAGIC: &[u8; 4] = b"WZ01";
+
+/// Version of the ".wz" format produced by [crate::compress]
+pub const FORMAT_VERSION: u8 = 3;
+
+/// Version of the ".wz" format produced when compressing against a reference module with
+/// [crate::compress::compress_bytes_with_reference]
+pub const DELTA_FORMAT_VERSION: u8 = 4;
+
+/// Length in bytes of the SHA-256 hash of the decompressed module stored in the header
+pub const HASH_LENGTH: usize = 32;
+
+/// Length in bytes of the header
+pub const HEADER_LENGTH: usize = MAGIC.len() + 3 + 8 + HASH_LENGTH;
+
+/// Mask for the bits of the flags that hold the compression level
+pub const LEVEL_MASK: u8 = 0x0f;
+
+/// Flag set when custom sections with debug information were stripped
+pub const FLAG_STRIPPED_DEBUG: u8 = 0x10;
+
+/// Flag set when all custom sections were stripped
+pub const FLAG_STRIPPED_ALL: u8 = 0x20;
+
+/// Flag set when duplicate types were removed from the module
+pub const FLAG_DEDUPLICATED: u8 = 0x40;
+
+/// Flag set when unreachable functions were removed from the module
+pub const FLAG_STRIPPED_UNUSED: u8 = 0x80;
+
+/// The header of a ".wz" file
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Header {
+    /// Version of the format
+    pub version: u8,
+    /// The compression level in the [LEVEL_MASK] bits, plus any of the `FLAG_*` flags
+    pub flags: u8,
+    /// Id of the dictionary used to compress the sections
+    pub dictionary_id: u8,
+    /// Length in bytes of the decompressed module
+    pub length: u64,
+    /// SHA-256 hash of the decompressed module
+    pub hash: [u8; HASH_LENGTH],
+}
+
+impl Header {
+    /// The compression level the file was compressed with
+    pub fn level(&self) -> u8 {
+        self.flags & LEVEL_MASK
+    }
+}
+
+/// Append the encoded `header` to `output`
+pub fn write_header(output: &mut Vec<u8>, header: &Header) {
+    output.extend_from_slice(MAGIC);
+    output.push(header.version);
+    output.push(header.flags);
+    output.push(header.dictionary_id);
+    output.extend_from_slice(&header.length.to_le_bytes());
+    output.extend_from_slice(&header.hash);
+}
+
+/// Read the header from the start of `input`
+/// Return a Result with the header and the rest of `input` that follows it
+pub fn read_header(input: &[u8]) -> Result<(Header, &[u8])> {
+    if !input.starts_with(MAGIC) {
+        return Err(Error::NotWazm);
+    }
+    match input.get(MAGIC.len()) {
+        Some(&FORMAT_VERSION) | Some(&DELTA_FORMAT_VERSION) => {},
+        Some(version) => return Err(Error::UnsupportedFormatVersion(*version)),
+        None => return Err(Error::TruncatedHeader),
+    }
+    if input.len() < HEADER_LENGTH {
+        return Err(Error::TruncatedHeader);
+    }
+
+    let (header, rest) = input.split_at(HEADER_LENGTH);
+    let mut length = [0; 8];
+    length.copy_from_slice(&header[7..15]);
+    let mut hash = [0; HASH_LENGTH];
+    hash.copy_from_slice(&header[15..]);
+
+    Ok((Header {
+        version: header[4],
+        flags: header[5],
+        dictionary_id: header[6],
+        length: u64::from_le_bytes(length),
+        hash,
+    }, rest))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{read_header, write_header, Header, DELTA_FORMAT_VERSION, FLAG_DEDUPLICATED,
+                FORMAT_VERSION, HEADER_LENGTH, MAGIC};
+    use crate::errors::Error;
+
+    fn header() -> Header {
+        Header {
+            version: FORMAT_VERSION,
+            flags: 7 | FLAG_DEDUPLICATED,
+            dictionary_id: 1,
+            length: 0x0102_0304_0506,
+            hash: [0xab; 32],
+        }
+    }
+
+    #[test]
+    fn test_header_round_trip() {
+        let mut output = vec![];
+        write_header(&mut output, &header());
+        assert_eq!(output.len(), HEADER_LENGTH);
+        output.extend_from_slice(b"sections");
+
+        let (read, rest) = read_header(&output).expect("Could not read header");
+        assert_eq!(read, header());
+        assert_eq!(read.level(), 7);
+        assert_eq!(rest, b"sections");
+    }
+
+    #[test]
+    fn test_not_a_wazm_file() {
+        let error = read_header(b"\0asm\x01\0\0\0").expect_err("Read header of a wasm file");
+        assert!(matches!(error, Error::NotWazm));
+        assert_eq!(error.to_string(), "Not a wazm file");
+    }
+
+    #[test]
+    fn test_unknown_version() {
+        let mut output = vec![];
+        write_header(&mut output, &Header { version: DELTA_FORMAT_VERSION + 1, ..header() });
+        assert!(matches!(read_header(&output),
+                         Err(Error::UnsupportedFormatVersion(version))
+                            if version == DELTA_FORMAT_VERSION + 1));
+    }
+
+    #[test]
+    fn test_delta_version() {
+        let mut output = vec![];
+        let delta = Header { version: DELTA_FORMAT_VERSION, ..header() };
+        write_header(&mut output, &delta);
+        let (read, _) = read_header(&output).expect("Could not read header");
+        assert_eq!(read, delta);
+    }
+
+    #[test]
+    fn test_truncated_header() {
+        let mut output = vec![];
+        write_header(&mut output, &header());
+        assert!(matches!(read_header(&output[..HEADER_LENGTH - 1]), Err(Error::TruncatedHeader)));
+        assert!(matches!(read_header(MAGIC), Err(Error::TruncatedHeader)));
+    }
+}
@@ -7,13 +7,28 @@ pub mod analysis;
 /// A Module for compressing ".wasm" files into ".wz"
 pub mod compress;
 
+/// A Module with the dictionaries used when compressing ".wz" files
+pub mod dictionary;
+
+/// A Module for disassembling the functions of a wasm module into readable text
+pub mod disassemble;
+
+/// A Module defining the header of ".wz" files
+pub mod header;
+
 /// A Module for decompressing ".wz" files into ".wasm"
 pub mod decompress;
 
+/// A Module with transforms that optimize a wasm module before compressing it
+pub mod optimize;
+
 /// A Module to parse a wasm source file
 pub mod parse;
 
-pub use analysis::analyze;
-pub use parse::Module;
-pub use compress::compress;
-pub use decompress::decompress;
+/// A Module for splitting a wasm module into one file per section
+pub mod split;
+
+pub use analysis::{analyze, analyze_with_jobs};
+pub use parse::{AsModule, Module, OwnedModule};
+pub use compress::{compress, compress_bytes};
+pub use decompress::{decompress, decompress_bytes};
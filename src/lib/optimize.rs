@@ -0,0 +1,700 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::ops::Range;
+use wasm_encoder::{CodeSection, Encode, IndirectNameMap, Instruction, NameMap, NameSection,
+                   RawSection};
+use wasmparser::{BinaryReader, BlockType, CodeSectionReader, ConstExpr, CustomSectionReader,
+                 ElementItems, ElementSectionReader, ExportSectionReader, ExternalKind,
+                 FunctionSectionReader, GlobalSectionReader, ImportSectionReader, Name,
+                 NameSectionReader, Operator, TagSectionReader, Type, TypeRef, TypeSectionReader};
+use wasmparser::Payload::{CodeSectionEntry, ElementSection, FunctionSection, GlobalSection,
+                          ImportSection, TagSection};
+use crate::analysis::analyze;
+use crate::errors::*;
+use crate::parse::validate;
+use crate::Module;
+
+// The new index of a function or type that has been removed, which is never referred to
+const REMOVED: u32 = u32::MAX;
+
+/// Which optimizations to apply to a module before compressing it
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Optimize {
+    /// Remove unreachable functions with [strip_unused_functions]
+    pub strip_unused: bool,
+    /// Remove duplicate types with [deduplicate_types]
+    pub deduplicate_types: bool,
+}
+
+impl Optimize {
+    /// Return true if no optimizations are selected
+    pub fn is_none(&self) -> bool {
+        !self.strip_unused && !self.deduplicate_types
+    }
+
+    /// Apply the selected optimizations to the module in `buf`, stripping unused functions
+    /// before deduplicating types.
+    /// Return a Result with the bytes of the optimized module
+    pub fn apply(&self, buf: &[u8]) -> Result<Vec<u8>> {
+        let mut output = buf.to_vec();
+        if self.strip_unused {
+            output = strip_unused_functions(&output)?;
+        }
+        if self.deduplicate_types {
+            output = deduplicate_types(&output)?;
+        }
+        Ok(output)
+    }
+}
+
+/// Deduplicate identical function signatures in the type section of the module in `buf`,
+/// rewriting all references to a removed type to use the first identical one, including the
+/// type names in the "name" section.
+/// Return a Result with the bytes of the rewritten module, which is a copy of `buf` if
+/// there were no duplicate types
+pub fn deduplicate_types(buf: &[u8]) -> Result<Vec<u8>> {
+    validate(buf).chain_err(|| "Cannot deduplicate the types of an invalid module")?;
+
+    let module = Module::from_bytes(buf)?;
+    let sections = module.sections()?;
+
+    let type_section = match sections.iter().find(|(id, _)| *id == 1) {
+        Some((_, range)) => range.clone(),
+        None => return Ok(buf.to_vec()),
+    };
+    let (unique_types, remap) = unique_types(buf, type_section)?;
+    if unique_types.len() == remap.len() {
+        return Ok(buf.to_vec());
+    }
+
+    let mut output = wasm_encoder::Module::new();
+    for (id, range) in sections {
+        let data = match id {
+            0 => {
+                let custom = CustomSectionReader::new(&buf[range.clone()], range.start)?;
+                if custom.name() == "name" {
+                    output.section(&rewrite_names(&custom, None, &remap)?);
+                    continue;
+                }
+                buf[range].to_vec()
+            },
+            1 => type_section_contents(buf, &unique_types),
+            2 => rewrite_imports(buf, range, &remap)?,
+            3 => rewrite_functions(buf, range, &remap, |_| true)?,
+            10 => {
+                output.section(&rewrite_code(buf, range, |_| true,
+                                             |operator| rewrite_operator(operator, &remap))?);
+                continue;
+            },
+            13 => rewrite_tags(buf, range, &remap)?,
+            _ => buf[range].to_vec(),
+        };
+        output.section(&RawSection { id, data: &data });
+    }
+
+    Ok(output.finish())
+}
+
+/// Remove the functions of the module in `buf` that can't be reached by following static
+/// calls from its exports, start function, element segments and `ref.func` operators, along
+/// with any types only they used. The remaining functions and types are renumbered everywhere
+/// they are referred to, including the "name" section. The ".debug_*" sections describe the
+/// code before it was rewritten, so they are dropped.
+/// Return a Result with the bytes of the rewritten module, which is a copy of `buf` if all
+/// functions can be reached
+pub fn strip_unused_functions(buf: &[u8]) -> Result<Vec<u8>> {
+    validate(buf).chain_err(|| "Cannot strip unused functions from an invalid module")?;
+
+    let module = Module::from_bytes(buf)?;
+    let analysis = analyze(&module, false, true, false, false)?;
+    let reachable = analysis.reachable_functions(referenced_functions(&module)?);
+    let imported_count = analysis.imported_functions.len();
+
+    // imported functions are always kept, as removing them would change the imports needed
+    let mut function_remap = vec![];
+    let mut kept_count = 0;
+    for index in 0..imported_count + analysis.implemented_function_count as usize {
+        if index < imported_count || reachable.contains(&index) {
+            function_remap.push(kept_count);
+            kept_count += 1;
+        } else {
+            function_remap.push(REMOVED);
+        }
+    }
+    if kept_count as usize == function_remap.len() {
+        return Ok(buf.to_vec());
+    }
+    let keep_body = |body_index: usize| function_remap[imported_count + body_index] != REMOVED;
+
+    let sections = module.sections()?;
+    let (types, type_remap) = match sections.iter().find(|(id, _)| *id == 1) {
+        Some((_, range)) => used_types(buf, range.clone(), &module, keep_body)?,
+        None => (vec![], vec![]),
+    };
+
+    let mut output = wasm_encoder::Module::new();
+    for (id, range) in sections {
+        let data = match id {
+            0 => {
+                let custom = CustomSectionReader::new(&buf[range.clone()], range.start)?;
+                if custom.name() == "name" {
+                    output.section(&rewrite_names(&custom, Some(&function_remap), &type_remap)?);
+                    continue;
+                } else if custom.name().starts_with(".debug_") {
+                    continue;
+                }
+                buf[range].to_vec()
+            },
+            1 => type_section_contents(buf, &types),
+            2 => rewrite_imports(buf, range, &type_remap)?,
+            3 => rewrite_functions(buf, range, &type_remap, keep_body)?,
+            6 => rewrite_globals(buf, range, &function_remap)?,
+            7 => rewrite_exports(buf, range, &function_remap)?,
+            8 => {
+                let start = BinaryReader::new_with_offset(&buf[range.clone()], range.start)
+                    .read_var_u32()?;
+                let mut data = vec![];
+                function_remap[start as usize].encode(&mut data);
+                data
+            },
+            9 => rewrite_elements(buf, range, &function_remap)?,
+            10 => {
+                output.section(&rewrite_code(buf, range, keep_body, |operator|
+                    rewrite_operator(operator, &type_remap)
+                        .or_else(|| rewrite_function_operator(operator, &function_remap)))?);
+                continue;
+            },
+            13 => rewrite_tags(buf, range, &type_remap)?,
+            _ => buf[range].to_vec(),
+        };
+        output.section(&RawSection { id, data: &data });
+    }
+
+    let output = output.finish();
+    validate(&output).chain_err(|| "Stripping unused functions produced an invalid module")?;
+    Ok(output)
+}
+
+// Find the functions referred to by `ref.func` in function bodies, globals or element
+// segments, which may be called through a reference from anywhere
+fn referenced_functions(module: &Module) -> Result<BTreeSet<usize>> {
+    let mut referenced = BTreeSet::new();
+    let mut add_references = |mut operators: wasmparser::OperatorsReader| -> Result<()> {
+        while !operators.eof() {
+            if let Operator::RefFunc { function_index } = operators.read()? {
+                referenced.insert(function_index as usize);
+            }
+        }
+        Ok(())
+    };
+
+    for payload in &module.payloads {
+        match payload {
+            CodeSectionEntry(body) => add_references(body.get_operators_reader()?)?,
+            GlobalSection(reader) => for global in reader.clone() {
+                add_references(global?.init_expr.get_operators_reader())?;
+            },
+            ElementSection(reader) => for element in reader.clone() {
+                if let ElementItems::Expressions(expressions) = element?.items {
+                    for expression in expressions {
+                        add_references(expression?.get_operators_reader())?;
+                    }
+                }
+            },
+            _ => {},
+        }
+    }
+
+    Ok(referenced)
+}
+
+// Find the byte ranges of the types in the type section that are still used once the function
+// bodies for which `keep_body` returns false are removed, and the index each of the original
+// types maps to. Unused types map to [REMOVED]
+fn used_types(buf: &[u8], range: Range<usize>, module: &Module,
+              keep_body: impl Fn(usize) -> bool) -> Result<(Vec<Range<usize>>, Vec<u32>)> {
+    let mut used = BTreeSet::new();
+    let mut body_index = 0;
+    for payload in &module.payloads {
+        match payload {
+            ImportSection(reader) => for import in reader.clone() {
+                match import?.ty {
+                    TypeRef::Func(type_index) => { used.insert(type_index); },
+                    TypeRef::Tag(tag) => { used.insert(tag.func_type_idx); },
+                    _ => {},
+                }
+            },
+            FunctionSection(reader) => for (index, type_index) in reader.clone().into_iter()
+                .enumerate() {
+                if keep_body(index) {
+                    used.insert(type_index?);
+                }
+            },
+            TagSection(reader) => for tag in reader.clone() {
+                used.insert(tag?.func_type_idx);
+            },
+            CodeSectionEntry(body) => {
+                if keep_body(body_index) {
+                    for operator in body.get_operators_reader()? {
+                        used.extend(operator_type(&operator?));
+                    }
+                }
+                body_index += 1;
+            },
+            _ => {},
+        }
+    }
+
+    let end = range.end;
+    let reader = TypeSectionReader::new(&buf[range.clone()], range.start)?;
+    let mut types = vec![];
+    let mut remap = vec![];
+    for (index, (_, range)) in item_ranges(reader.into_iter_with_offsets(), end)?
+        .into_iter().enumerate() {
+        if used.contains(&(index as u32)) {
+            remap.push(types.len() as u32);
+            types.push(range);
+        } else {
+            remap.push(REMOVED);
+        }
+    }
+
+    Ok((types, remap))
+}
+
+// Find the byte ranges of the first definition of each distinct type in the type section
+// and the index each of the original types maps to in the deduplicated type section
+fn unique_types(buf: &[u8], range: Range<usize>) -> Result<(Vec<Range<usize>>, Vec<u32>)> {
+    let end = range.end;
+    let reader = TypeSectionReader::new(&buf[range.clone()], range.start)?;
+    let items = item_ranges(reader.into_iter_with_offsets(), end)?;
+
+    let mut first_definitions: HashMap<_, u32> = HashMap::new();
+    let mut unique_types = vec![];
+    let mut remap = vec![];
+    for (ty, range) in items {
+        let func_type = match ty {
+            Type::Func(func_type) => func_type,
+            Type::Array(_) => return Err(Error::UnsupportedArrayType),
+        };
+        let index = *first_definitions.entry(func_type).or_insert_with(|| {
+            unique_types.push(range);
+            unique_types.len() as u32 - 1
+        });
+        remap.push(index);
+    }
+
+    Ok((unique_types, remap))
+}
+
+// Pair each item read from a section with the byte range it was read from
+fn item_ranges<T>(items: impl Iterator<Item = wasmparser::Result<(usize, T)>>, end: usize)
+    -> Result<Vec<(T, Range<usize>)>> {
+    let mut item_ranges: Vec<(T, Range<usize>)> = vec![];
+    for item in items {
+        let (offset, item) = item?;
+        if let Some((_, previous)) = item_ranges.last_mut() {
+            previous.end = offset;
+        }
+        item_ranges.push((item, offset..end));
+    }
+    Ok(item_ranges)
+}
+
+// Build the contents of a section from the count of items and their encoded bytes
+fn section_contents(count: u32, items: &[u8]) -> Vec<u8> {
+    let mut contents = vec![];
+    count.encode(&mut contents);
+    contents.extend_from_slice(items);
+    contents
+}
+
+// Build the contents of a type section from the byte ranges of the types to keep
+fn type_section_contents(buf: &[u8], types: &[Range<usize>]) -> Vec<u8> {
+    let mut items = vec![];
+    for range in types {
+        items.extend_from_slice(&buf[range.clone()]);
+    }
+    section_contents(types.len() as u32, &items)
+}
+
+// Rewrite the type of imported functions and tags
+fn rewrite_imports(buf: &[u8], range: Range<usize>, remap: &[u32]) -> Result<Vec<u8>> {
+    let end = range.end;
+    let reader = ImportSectionReader::new(&buf[range.clone()], range.start)?;
+    let count = reader.count();
+    let mut items = vec![];
+    for (import, range) in item_ranges(reader.into_iter_with_offsets(), end)? {
+        match import.ty {
+            TypeRef::Func(type_index) => {
+                import.module.encode(&mut items);
+                import.name.encode(&mut items);
+                items.push(0x00);
+                remap[type_index as usize].encode(&mut items);
+            },
+            TypeRef::Tag(tag) => {
+                import.module.encode(&mut items);
+                import.name.encode(&mut items);
+                items.extend_from_slice(&[0x04, 0x00]);
+                remap[tag.func_type_idx as usize].encode(&mut items);
+            },
+            _ => items.extend_from_slice(&buf[range]),
+        }
+    }
+    Ok(section_contents(count, &items))
+}
+
+// Rewrite the type of the implemented functions for which `keep` returns true when given
+// their position in the function section, removing the others
+fn rewrite_functions(buf: &[u8], range: Range<usize>, remap: &[u32], keep: impl Fn(usize) -> bool)
+    -> Result<Vec<u8>> {
+    let reader = FunctionSectionReader::new(&buf[range.clone()], range.start)?;
+    let mut count = 0;
+    let mut items = vec![];
+    for (index, type_index) in reader.into_iter().enumerate() {
+        let type_index = type_index?;
+        if keep(index) {
+            remap[type_index as usize].encode(&mut items);
+            count += 1;
+        }
+    }
+    Ok(section_contents(count, &items))
+}
+
+// Rewrite the type of exception tags
+fn rewrite_tags(buf: &[u8], range: Range<usize>, remap: &[u32]) -> Result<Vec<u8>> {
+    let reader = TagSectionReader::new(&buf[range.clone()], range.start)?;
+    let count = reader.count();
+    let mut items = vec![];
+    for tag in reader {
+        items.push(0x00);
+        remap[tag?.func_type_idx as usize].encode(&mut items);
+    }
+    Ok(section_contents(count, &items))
+}
+
+// Rewrite the function index of exported functions
+fn rewrite_exports(buf: &[u8], range: Range<usize>, remap: &[u32]) -> Result<Vec<u8>> {
+    let end = range.end;
+    let reader = ExportSectionReader::new(&buf[range.clone()], range.start)?;
+    let count = reader.count();
+    let mut items = vec![];
+    for (export, range) in item_ranges(reader.into_iter_with_offsets(), end)? {
+        match export.kind {
+            ExternalKind::Func => {
+                export.name.encode(&mut items);
+                items.push(0x00);
+                remap[export.index as usize].encode(&mut items);
+            },
+            _ => items.extend_from_slice(&buf[range]),
+        }
+    }
+    Ok(section_contents(count, &items))
+}
+
+// Rewrite the functions referred to in the initial value of globals
+fn rewrite_globals(buf: &[u8], range: Range<usize>, remap: &[u32]) -> Result<Vec<u8>> {
+    let end = range.end;
+    let reader = GlobalSectionReader::new(&buf[range.clone()], range.start)?;
+    let count = reader.count();
+    let mut items = vec![];
+    for (global, range) in item_ranges(reader.into_iter_with_offsets(), end)? {
+        let init_start = global.init_expr.get_binary_reader().original_position();
+        items.extend_from_slice(&buf[range.start..init_start]);
+        items.extend(rewrite_const_expr(buf, &global.init_expr, remap)?);
+    }
+    Ok(section_contents(count, &items))
+}
+
+// Rewrite the functions in element segments, copying the kind and type of each segment unchanged
+fn rewrite_elements(buf: &[u8], range: Range<usize>, remap: &[u32]) -> Result<Vec<u8>> {
+    let reader = ElementSectionReader::new(&buf[range.clone()], range.start)?;
+    let count = reader.count();
+    let mut items = vec![];
+    for element in reader {
+        let element = element?;
+        // the range of the items starts with their count
+        match element.items {
+            ElementItems::Functions(functions) => {
+                items.extend_from_slice(&buf[element.range.start..functions.range().start]);
+                functions.count().encode(&mut items);
+                for function_index in functions {
+                    remap[function_index? as usize].encode(&mut items);
+                }
+            },
+            ElementItems::Expressions(expressions) => {
+                items.extend_from_slice(&buf[element.range.start..expressions.range().start]);
+                expressions.count().encode(&mut items);
+                for expression in expressions {
+                    items.extend(rewrite_const_expr(buf, &expression?, remap)?);
+                }
+            },
+        }
+    }
+    Ok(section_contents(count, &items))
+}
+
+// Rewrite a constant expression, copying every operator that doesn't refer to a function
+fn rewrite_const_expr(buf: &[u8], expression: &ConstExpr, remap: &[u32]) -> Result<Vec<u8>> {
+    let mut bytes = vec![];
+    let mut operators = expression.get_operators_reader();
+    while !operators.eof() {
+        let start = operators.original_position();
+        let operator = operators.read()?;
+        match rewrite_function_operator(&operator, remap) {
+            Some(instruction) => instruction.encode(&mut bytes),
+            None => bytes.extend_from_slice(&buf[start..operators.original_position()]),
+        }
+    }
+    Ok(bytes)
+}
+
+// Rewrite the function bodies for which `keep` returns true when given their position in the
+// code section, removing the others. Operators `rewrite` returns a replacement for are
+// replaced and every other operator is copied unchanged
+fn rewrite_code(buf: &[u8], range: Range<usize>, keep: impl Fn(usize) -> bool,
+                rewrite: impl Fn(&Operator) -> Option<Instruction<'static>>)
+    -> Result<CodeSection> {
+    let reader = CodeSectionReader::new(&buf[range.clone()], range.start)?;
+    let mut code = CodeSection::new();
+    for (index, body) in reader.into_iter().enumerate() {
+        let body = body?;
+        if !keep(index) {
+            continue;
+        }
+        let mut operators = body.get_operators_reader()?;
+        let mut new_body = buf[body.range().start..operators.original_position()].to_vec();
+        while !operators.eof() {
+            let start = operators.original_position();
+            let operator = operators.read()?;
+            match rewrite(&operator) {
+                Some(instruction) => instruction.encode(&mut new_body),
+                None => new_body.extend_from_slice(&buf[start..operators.original_position()]),
+            }
+        }
+        code.raw(&new_body);
+    }
+    Ok(code)
+}
+
+// Return the type an operator refers to, if it refers to one
+fn operator_type(operator: &Operator) -> Option<u32> {
+    match operator {
+        Operator::Block { blockty: BlockType::FuncType(type_index) } |
+        Operator::Loop { blockty: BlockType::FuncType(type_index) } |
+        Operator::If { blockty: BlockType::FuncType(type_index) } |
+        Operator::Try { blockty: BlockType::FuncType(type_index) } |
+        Operator::CallIndirect { type_index, .. } |
+        Operator::ReturnCallIndirect { type_index, .. } => Some(*type_index),
+        _ => None,
+    }
+}
+
+// Return a replacement for an operator that refers to a type, or None if it doesn't
+fn rewrite_operator(operator: &Operator, remap: &[u32]) -> Option<Instruction<'static>> {
+    let block_type = |blockty: &BlockType| match blockty {
+        BlockType::FuncType(type_index) =>
+            Some(wasm_encoder::BlockType::FunctionType(remap[*type_index as usize])),
+        _ => None,
+    };
+
+    match operator {
+        Operator::Block { blockty } => block_type(blockty).map(Instruction::Block),
+        Operator::Loop { blockty } => block_type(blockty).map(Instruction::Loop),
+        Operator::If { blockty } => block_type(blockty).map(Instruction::If),
+        Operator::Try { blockty } => block_type(blockty).map(Instruction::Try),
+        Operator::CallIndirect { type_index, table_index, .. } => Some(Instruction::CallIndirect {
+            ty: remap[*type_index as usize],
+            table: *table_index,
+        }),
+        Operator::ReturnCallIndirect { type_index, table_index } =>
+            Some(Instruction::ReturnCallIndirect {
+                ty: remap[*type_index as usize],
+                table: *table_index,
+            }),
+        _ => None,
+    }
+}
+
+// Return a replacement for an operator that refers to a function, or None if it doesn't
+fn rewrite_function_operator(operator: &Operator, remap: &[u32]) -> Option<Instruction<'static>> {
+    match operator {
+        Operator::Call { function_index } =>
+            Some(Instruction::Call(remap[*function_index as usize])),
+        Operator::ReturnCall { function_index } =>
+            Some(Instruction::ReturnCall(remap[*function_index as usize])),
+        Operator::RefFunc { function_index } =>
+            Some(Instruction::RefFunc(remap[*function_index as usize])),
+        _ => None,
+    }
+}
+
+// Rewrite the "name" section, renumbering the names of functions, their locals and labels, and
+// types, dropping the names of any that were removed. Functions are not renumbered if there is
+// no `function_remap`. Subsections wasm_encoder can't encode are dropped
+fn rewrite_names(reader: &CustomSectionReader, function_remap: Option<&[u32]>,
+                 type_remap: &[u32]) -> Result<NameSection> {
+    let mut names = NameSection::new();
+    for name in NameSectionReader::new(reader.data(), reader.data_offset()) {
+        match name? {
+            Name::Module { name, .. } => names.module(name),
+            Name::Function(map) => names.functions(&rewrite_name_map(map, function_remap)?),
+            Name::Local(map) => names.locals(&rewrite_indirect_name_map(map, function_remap)?),
+            Name::Label(map) => names.labels(&rewrite_indirect_name_map(map, function_remap)?),
+            Name::Type(map) => names.types(&rewrite_name_map(map, Some(type_remap))?),
+            Name::Table(map) => names.tables(&rewrite_name_map(map, None)?),
+            Name::Memory(map) => names.memories(&rewrite_name_map(map, None)?),
+            Name::Global(map) => names.globals(&rewrite_name_map(map, None)?),
+            Name::Element(map) => names.elements(&rewrite_name_map(map, None)?),
+            Name::Data(map) => names.data(&rewrite_name_map(map, None)?),
+            Name::Unknown { .. } => {},
+        }
+    }
+    Ok(names)
+}
+
+// Copy the names in `map`, renumbering them with `remap` if there is one. Names of removed
+// items, or of items that don't exist, are dropped, as are the names of any items after the
+// first that are renumbered to the same index
+fn rewrite_name_map(map: wasmparser::NameMap, remap: Option<&[u32]>) -> Result<NameMap> {
+    // a name map must be in index order, which renumbering may not keep
+    let mut renamed = BTreeMap::new();
+    for naming in map {
+        let naming = naming?;
+        let index = match remap {
+            None => naming.index,
+            Some(remap) => match remap.get(naming.index as usize) {
+                Some(&index) if index != REMOVED => index,
+                _ => continue,
+            },
+        };
+        renamed.entry(index).or_insert(naming.name);
+    }
+    let mut names = NameMap::new();
+    for (index, name) in renamed {
+        names.append(index, name);
+    }
+    Ok(names)
+}
+
+// Copy the names of the locals or labels of each function in `map`, renumbering the functions
+// with `remap` if there is one and dropping the names for removed functions
+fn rewrite_indirect_name_map(map: wasmparser::IndirectNameMap, remap: Option<&[u32]>)
+    -> Result<IndirectNameMap> {
+    let mut names = IndirectNameMap::new();
+    for naming in map {
+        let naming = naming?;
+        let index = match remap {
+            None => naming.index,
+            Some(remap) => match remap.get(naming.index as usize) {
+                Some(&index) if index != REMOVED => index,
+                _ => continue,
+            },
+        };
+        names.append(index, &rewrite_name_map(naming.names, None)?);
+    }
+    Ok(names)
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+    use std::path::PathBuf;
+    use wasmparser::Payload::{CodeSectionEntry, CustomSection, FunctionSection, ImportSection,
+                              TypeSection};
+    use wasmparser::{Name, NameSectionReader, Operator, TypeRef};
+    use crate::Module;
+    use crate::parse::wat_to_wasm;
+    use super::{deduplicate_types, strip_unused_functions};
+
+    #[test]
+    fn test_deduplicate_types() {
+        let source = PathBuf::from(&format!("{}/tests/test_files/duplicate_signatures.wat",
+                                            env!("CARGO_MANIFEST_DIR")));
+        let text = fs::read_to_string(source).expect("Could not read wat file");
+        let buf = wat_to_wasm(&text).expect("Could not compile wat");
+
+        let optimized = deduplicate_types(&buf).expect("Could not deduplicate types");
+        wasmparser::validate(&optimized).expect("Deduplicated module is not valid");
+
+        let module = Module::from_bytes(&optimized).expect("Could not parse optimized");
+        let mut function_types = vec![];
+        let mut type_operands = vec![];
+        let mut type_names = vec![];
+        for payload in &module.payloads {
+            match payload {
+                TypeSection(reader) => assert_eq!(reader.count(), 2),
+                ImportSection(reader) => for import in reader.clone() {
+                    if let TypeRef::Func(type_index) = import.expect("Bad import").ty {
+                        function_types.push(type_index);
+                    }
+                },
+                FunctionSection(reader) => for type_index in reader.clone() {
+                    function_types.push(type_index.expect("Bad function"));
+                },
+                CodeSectionEntry(body) => {
+                    for operator in body.get_operators_reader().expect("Bad body") {
+                        match operator.expect("Bad operator") {
+                            Operator::Block { blockty: wasmparser::BlockType::FuncType(ty) } |
+                            Operator::CallIndirect { type_index: ty, .. } =>
+                                type_operands.push(ty),
+                            _ => {},
+                        }
+                    }
+                },
+                CustomSection(reader) if reader.name() == "name" => {
+                    for name in NameSectionReader::new(reader.data(), reader.data_offset()) {
+                        if let Name::Type(map) = name.expect("Bad name") {
+                            for naming in map {
+                                let naming = naming.expect("Bad type name");
+                                type_names.push((naming.index, naming.name));
+                            }
+                        }
+                    }
+                },
+                _ => {},
+            }
+        }
+        assert_eq!(function_types, vec!(0, 1, 0, 1));
+        assert_eq!(type_operands, vec!(1, 1, 0));
+        // the names of removed types are dropped, and the rest renumbered
+        assert_eq!(type_names, vec!((0, "binary"), (1, "unary")));
+
+        // a module without duplicates is unchanged
+        assert_eq!(deduplicate_types(&optimized).expect("Could not deduplicate types"),
+                   optimized);
+    }
+
+    #[test]
+    fn test_strip_unused_functions() {
+        let source = PathBuf::from(&format!("{}/tests/test_files/unused_functions.wat",
+                                            env!("CARGO_MANIFEST_DIR")));
+        let text = fs::read_to_string(source).expect("Could not read wat file");
+        let buf = wat_to_wasm(&text).expect("Could not compile wat");
+
+        let stripped = strip_unused_functions(&buf).expect("Could not strip unused functions");
+        wasmparser::validate(&stripped).expect("Stripped module is not valid");
+
+        let type_count = |buf: &[u8]| Module::from_bytes(buf).expect("Could not parse")
+            .payloads.iter()
+            .find_map(|payload| match payload {
+                TypeSection(reader) => Some(reader.count()),
+                _ => None,
+            });
+        assert_eq!(type_count(&stripped), type_count(&buf).map(|count| count - 1));
+
+        let module = Module::from_bytes(&stripped).expect("Could not parse stripped");
+        let analysis = crate::analyze(&module, false, true, false, false)
+            .expect("Could not analyze stripped");
+        assert_eq!(analysis.implemented_function_count, 4);
+        assert_eq!(analysis.exported_functions.get(&4).map(String::as_str), Some("run"));
+        assert_eq!(analysis.function_names.get(&1).map(String::as_str), Some("helper"));
+        assert_eq!(analysis.function_names.get(&3).map(String::as_str), Some("referenced"));
+        assert_eq!(analysis.static_function_calls.get(&4), Some(&vec!(1)));
+        // only referred to by `ref.func`, which the analysis doesn't count as a call
+        assert_eq!(analysis.uncalled_functions, vec!(3));
+
+        // a module with only reachable functions is unchanged
+        assert_eq!(strip_unused_functions(&stripped).expect("Could not strip unused functions"),
+                   stripped);
+    }
+}
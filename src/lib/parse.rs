@@ -1,22 +1,55 @@
 use std::path::Path;
+use std::ops::Range;
 use crate::errors::*;
-use wasmparser::{Parser, Payload::*, Payload};
+use wasmparser::{BinaryReader, Chunk, CustomSectionReader, Encoding, FromReader,
+                 FunctionSectionReader, MemorySectionReader, Parser, Payload::*, Payload,
+                 SectionLimited, SectionLimitedIntoIter, Validator, WasmFeatures};
 use std::fmt;
+use wast::parser::ParseBuffer;
+use wast::Wat;
 use log::debug;
+use serde::Serialize;
+
+/// Whether a binary is a core wasm module or a component of the component model
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+pub enum ModuleKind {
+    #[default]
+    CoreModule,
+    Component,
+}
+
+impl fmt::Display for ModuleKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ModuleKind::CoreModule => write!(f, "core module"),
+            ModuleKind::Component => write!(f, "component"),
+        }
+    }
+}
 
 /// wasm Module
 pub struct Module<'a> {
     pub source: String,
+    pub kind: ModuleKind,
     pub version: u16,
     pub file_size: u64,
     pub payloads: Vec<Payload<'a>>,
+    buf: &'a [u8],
 }
 
 impl<'a> Module<'a> {
     fn add_payload(&mut self, payload: Payload<'a>) -> Result<()> {
         #[allow(unused_variables)]
         match &payload {
-            Version { num, encoding, range } => self.version = *num,
+            // Only the first Version payload is that of the binary itself, later ones are
+            // those of modules nested inside a component
+            Version { num, encoding, range } if self.payloads.is_empty() => {
+                self.version = *num;
+                self.kind = match encoding {
+                    Encoding::Module => ModuleKind::CoreModule,
+                    Encoding::Component => ModuleKind::Component,
+                };
+            },
             _ => debug!("Adding non-Version payload"),
         }
         self.payloads.push(payload);
@@ -26,37 +59,550 @@ impl<'a> Module<'a> {
     /// Check that a [Module] is valid
     pub fn validate(self) -> Result<Self> {
         if self.version == 0 {
-            bail!("Invalid WASM version in module");
+            return Err(Error::InvalidVersion);
         }
 
         Ok(self)
     }
 
-    /// Parse a source file on disk into a [Module}
-    pub fn parse(source: &Path, buf: &'a [u8]) -> Result<Self> {
-        let mut module = Self {
-            source: source.canonicalize()?.display().to_string(),
+    /// Get the id and byte range of the contents of each section of the [Module], in the order
+    /// in which they appear in the source
+    pub fn sections(&self) -> Result<Vec<(u8, Range<usize>)>> {
+        let mut sections = vec![];
+        for payload in &self.payloads {
+            #[allow(unused_variables)]
+            let section = match payload {
+                Version { encoding: Encoding::Component, .. } =>
+                    return Err(Error::UnsupportedComponent(
+                        "WASM components are not supported".into())),
+                Version { .. } | CodeSectionEntry(_) => continue,
+                CustomSection(reader) => (0, reader.range()),
+                TypeSection(reader) => (1, reader.range()),
+                ImportSection(reader) => (2, reader.range()),
+                FunctionSection(reader) => (3, reader.range()),
+                TableSection(reader) => (4, reader.range()),
+                MemorySection(reader) => (5, reader.range()),
+                GlobalSection(reader) => (6, reader.range()),
+                ExportSection(reader) => (7, reader.range()),
+                StartSection { func, range } => (8, range.clone()),
+                ElementSection(reader) => (9, reader.range()),
+                CodeSectionStart { count, range, size } => (10, range.clone()),
+                DataSection(reader) => (11, reader.range()),
+                DataCountSection { count, range } => (12, range.clone()),
+                TagSection(reader) => (13, reader.range()),
+                UnknownSection { id, contents, range } => (*id, range.clone()),
+                _ => bail!("Unexpected section in a WASM Module"),
+            };
+            sections.push(section);
+        }
+        Ok(sections)
+    }
+
+    /// Encode the [Module] back into WASM bytes, reproducing the module it was parsed from.
+    /// The function, memory, start and data count sections are encoded from what was parsed
+    /// from them, and the contents of any other section are copied unchanged
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut output = wasm_encoder::Module::new();
+        for (id, range) in self.sections()? {
+            let contents = &self.buf[range.clone()];
+            match id {
+                3 => {
+                    let mut functions = wasm_encoder::FunctionSection::new();
+                    for type_index in FunctionSectionReader::new(contents, range.start)? {
+                        functions.function(type_index?);
+                    }
+                    output.section(&functions);
+                },
+                5 => {
+                    let mut memories = wasm_encoder::MemorySection::new();
+                    for memory in MemorySectionReader::new(contents, range.start)? {
+                        let memory = memory?;
+                        memories.memory(wasm_encoder::MemoryType {
+                            minimum: memory.initial,
+                            maximum: memory.maximum,
+                            memory64: memory.memory64,
+                            shared: memory.shared,
+                        });
+                    }
+                    output.section(&memories);
+                },
+                8 => {
+                    let function_index = BinaryReader::new_with_offset(contents, range.start)
+                        .read_var_u32()?;
+                    output.section(&wasm_encoder::StartSection { function_index });
+                },
+                12 => {
+                    let count = BinaryReader::new_with_offset(contents, range.start)
+                        .read_var_u32()?;
+                    output.section(&wasm_encoder::DataCountSection { count });
+                },
+                _ => {
+                    output.section(&wasm_encoder::RawSection { id, data: contents });
+                },
+            }
+        }
+        Ok(output.finish())
+    }
+
+    /// Get the raw bytes of the contents of the first section of type `section_type`, named as
+    /// in [crate::analysis::SECTION_TYPES], without parsing it again.
+    /// Return None if there is no section of that type in the [Module]
+    pub fn section_bytes(&self, section_type: &str) -> Option<&'a [u8]> {
+        self.payloads.iter()
+            .filter_map(section_range)
+            .find(|(name, _)| *name == section_type)
+            .map(|(_, range)| &self.buf[range])
+    }
+
+    /// Iterate the items of the section read by `reader`, one of the [Module]'s payloads, reading
+    /// each of them once from the bytes of the module. The reader is left as it is, as
+    /// iterating it would consume it and it is borrowed from the payloads
+    pub fn section_items<T: FromReader<'a>>(&self, reader: &SectionLimited<'a, T>)
+        -> Result<SectionLimitedIntoIter<'a, T>> {
+        let range = reader.range();
+        Ok(SectionLimited::new(&self.buf[range.clone()], range.start)?.into_iter())
+    }
+
+    /// Get the raw bytes of each whole section of the [Module], including the id and size that
+    /// precede its contents, in the order in which they appear in the source, with the name of
+    /// the file to split it into. Files are named "NN_SectionType.bin" from the position of
+    /// the section and its type, as named in [crate::analysis::SECTION_TYPES]. Custom sections
+    /// also have their name added, as in "NN_CustomSection_name.bin"
+    pub fn split(&self) -> Result<Vec<(String, &'a [u8])>> {
+        let section_types = self.payloads.iter()
+            .filter_map(section_range)
+            .map(|(section_type, _)| section_type);
+        // sections follow each other, starting immediately after the header
+        let mut start = match self.payloads.first() {
+            Some(Version { range, .. }) => range.end,
+            _ => return Err(Error::MissingHeader),
+        };
+
+        let mut files = vec![];
+        for (index, ((id, range), section_type)) in self.sections()?.into_iter()
+            .zip(section_types).enumerate() {
+            let mut file_name = format!("{:02}_{}", index, section_type);
+            if id == 0 {
+                let custom = CustomSectionReader::new(&self.buf[range.clone()], range.start)?;
+                file_name.push('_');
+                // a custom section may have any name, so only keep characters safe in a file name
+                file_name.extend(custom.name().chars().map(|c| match c {
+                    'a'..='z' | 'A'..='Z' | '0'..='9' | '.' | '-' | '_' => c,
+                    _ => '_',
+                }));
+            }
+            file_name.push_str(".bin");
+            files.push((file_name, &self.buf[start..range.end]));
+            start = range.end;
+        }
+        Ok(files)
+    }
+
+    /// Read the source file on disk at `source` and parse it with [Module::from_source] into
+    /// an [OwnedModule] that owns the bytes read, so that it can be returned or stored without
+    /// borrowing a buffer from the caller. A ".wat" source file is compiled with [wat_to_wasm]
+    pub fn parse(source: &Path) -> Result<OwnedModule> {
+        let buf = if source.extension() == Some("wat".as_ref()) {
+            wat_to_wasm(&std::fs::read_to_string(source)?)?
+        } else {
+            std::fs::read(source)?
+        };
+        OwnedModule::new(buf, |buf| Module::from_source(source, buf))
+    }
+
+    /// Parse the WASM bytes in `buf`, that were read from the source file on disk at `source`,
+    /// or compiled from its text if it is a ".wat" file, into a [Module] with [Module::from_bytes].
+    /// The source is the canonical path of the file and the file size is its size on disk
+    pub fn from_source(source: &Path, buf: &'a [u8]) -> Result<Self> {
+        let mut module = Self::from_bytes(buf)?;
+        module.source = source.canonicalize()?.display().to_string();
+        module.file_size = source.metadata()?.len();
+        Ok(module)
+    }
+
+    /// Assemble WebAssembly text into WASM bytes with [wat_to_wasm] and parse them once into an
+    /// [OwnedModule] with "<wat>" as its source. A [Module] only borrows its bytes, so the
+    /// assembled bytes are returned with it rather than dropped at the end of this function
+    pub fn from_wat(text: &str) -> Result<OwnedModule> {
+        OwnedModule::new(wat_to_wasm(text)?, |buf| {
+            let mut module = Module::from_bytes(buf)?;
+            module.source = "<wat>".into();
+            Ok(module)
+        })
+    }
+
+    /// Parse a source file on disk into a [Module], after fully validating it with
+    /// [wasmparser::Validator] so that malformed modules are rejected before any analysis
+    pub fn parse_validated(source: &Path, buf: &'a [u8]) -> Result<Self> {
+        if let Err(e) = validate(buf) {
+            return Err(Error::Parse {
+                offset: e.offset(),
+                message: format!("invalid WASM module '{}': {}", source.display(), e.message()),
+            });
+        }
+
+        Self::from_source(source, buf)
+    }
+
+    /// Parse a [Module] from WASM bytes in memory, that were not read from a source file
+    pub fn from_bytes(buf: &'a [u8]) -> Result<Self> {
+        Self::from_range(buf, 0..buf.len())
+    }
+
+    /// Parse the core modules in `buf`. That is the module itself if it is a core module,
+    /// or each of the core modules nested at any depth inside it if it is a component, in
+    /// the order they appear in it. The offsets in each [Module] are from the start of `buf`
+    pub fn parse_all(buf: &'a [u8]) -> Result<Vec<Self>> {
+        let module = Self::from_bytes(buf)?;
+        if module.kind == ModuleKind::CoreModule {
+            return Ok(vec![module]);
+        }
+
+        let mut modules = vec![];
+        for payload in &module.payloads {
+            if let ModuleSection { range, .. } = payload {
+                modules.push(Self::from_range(buf, range.clone())?);
+            }
+        }
+        Ok(modules)
+    }
+
+    // Parse the module in `range` of `buf`
+    fn from_range(buf: &'a [u8], range: Range<usize>) -> Result<Self> {
+        let module = Self {
+            source: "<memory>".into(),
+            kind: ModuleKind::CoreModule,
             version: 0,
-            file_size: source.metadata()?.len(),
+            file_size: range.len() as u64,
             payloads: vec![],
+            buf,
         };
 
-        for payload in Parser::new(0).parse_all(buf) {
+        module.add_payloads(range)
+    }
+
+    // Parse all the payloads in `range` of the module's bytes, including those of nested
+    // modules, and add them to the module, then validate it. Each payload is first parsed as
+    // if more input could follow it, so that input that ends part way through a payload is
+    // reported as truncated
+    fn add_payloads(mut self, range: Range<usize>) -> Result<Self> {
+        let mut parsers = vec![];
+        let mut parser = Parser::new(range.start as u64);
+        let mut data = &self.buf[range.clone()];
+        loop {
+            let chunk = match parser.parse(data, false) {
+                Ok(Chunk::NeedMoreData(_)) => parser.parse(data, true)
+                    .map_err(|_| Error::Truncated(range.end - data.len()))?,
+                Ok(chunk) => chunk,
+                Err(e) => return Err(e.into()),
+            };
+            let payload = match chunk {
+                Chunk::Parsed { consumed, payload } => {
+                    data = &data[consumed..];
+                    payload
+                },
+                Chunk::NeedMoreData(_) => unreachable!("No more data is needed at the end"),
+            };
+
             match payload {
-                Ok(End(_)) => continue,
-                Ok(section) => module.add_payload(section)?,
-                _ => bail!("Unexpected payload while parsing WASM Module"),
+                ModuleSection { parser: ref nested, .. } |
+                ComponentSection { parser: ref nested, .. } => {
+                    parsers.push(parser.clone());
+                    parser = nested.clone();
+                    self.add_payload(payload)?;
+                },
+                End(_) => match parsers.pop() {
+                    Some(outer) => parser = outer,
+                    None => break,
+                },
+                _ => self.add_payload(payload)?,
             }
         }
 
-        module.validate()
+        self.validate()
+    }
+}
+
+/// The WebAssembly proposals accepted when validating a module, which are those enabled by
+/// default in [WasmFeatures] and some more that [crate::optimize] can rewrite. Typed function
+/// references are not accepted, as they can refer to types and functions from many more places
+pub fn features() -> WasmFeatures {
+    WasmFeatures {
+        threads: true,
+        exceptions: true,
+        multi_memory: true,
+        memory64: true,
+        extended_const: true,
+        relaxed_simd: true,
+        tail_call: true,
+        ..WasmFeatures::default()
+    }
+}
+
+/// Fully validate the module in `buf`, accepting the proposals in [features]
+pub fn validate(buf: &[u8]) -> wasmparser::Result<()> {
+    Validator::new_with_features(features()).validate_all(buf).map(|_| ())
+}
+
+/// Compile WebAssembly text into the bytes of a WASM module or component
+pub fn wat_to_wasm(text: &str) -> Result<Vec<u8>> {
+    let wat_error = |e: wast::Error| {
+        let (line, column) = e.span().linecol_in(text);
+        Error::Wat { line: line + 1, column: column + 1, message: e.message() }
+    };
+    let buf = ParseBuffer::new(text).map_err(wat_error)?;
+    let mut wat = wast::parser::parse::<Wat>(&buf).map_err(wat_error)?;
+    wat.encode().map_err(wat_error)
+}
+
+// The type of the section a payload is for and the byte range of its contents, or None if
+// the payload is not a section
+#[allow(unused_variables)]
+fn section_range(payload: &Payload) -> Option<(&'static str, Range<usize>)> {
+    Some(match payload {
+        TypeSection(reader) => ("TypeSection", reader.range()),
+        ImportSection(reader) => ("ImportSection", reader.range()),
+        FunctionSection(reader) => ("FunctionSection", reader.range()),
+        TableSection(reader) => ("TableSection", reader.range()),
+        MemorySection(reader) => ("MemorySection", reader.range()),
+        TagSection(reader) => ("TagSection", reader.range()),
+        GlobalSection(reader) => ("GlobalSection", reader.range()),
+        ExportSection(reader) => ("ExportSection", reader.range()),
+        StartSection { func, range } => ("StartSection", range.clone()),
+        ElementSection(reader) => ("ElementSection", reader.range()),
+        DataCountSection { count, range } => ("DataCountSection", range.clone()),
+        CodeSectionStart { count, range, size } => ("CodeSectionStart", range.clone()),
+        DataSection(reader) => ("DataSection", reader.range()),
+        CustomSection(reader) => ("CustomSection", reader.range()),
+        UnknownSection { id, contents, range } => ("UnknownSection", range.clone()),
+        ModuleSection { parser, range } => ("ModuleSection", range.clone()),
+        InstanceSection(reader) => ("InstanceSection", reader.range()),
+        CoreTypeSection(reader) => ("CoreTypeSection", reader.range()),
+        ComponentSection { parser, range } => ("ComponentSection", range.clone()),
+        ComponentInstanceSection(reader) => ("ComponentInstanceSection", reader.range()),
+        ComponentAliasSection(reader) => ("ComponentAliasSection", reader.range()),
+        ComponentTypeSection(reader) => ("ComponentTypeSection", reader.range()),
+        ComponentCanonicalSection(reader) => ("ComponentCanonicalSection", reader.range()),
+        ComponentStartSection { start, range } => ("ComponentStartSection", range.clone()),
+        ComponentImportSection(reader) => ("ComponentImportSection", reader.range()),
+        ComponentExportSection(reader) => ("ComponentExportSection", reader.range()),
+        _ => return None,
+    })
+}
+
+/// A parsed wasm module that owns the bytes it was parsed from, so it is only parsed once
+/// however many times it is used
+pub struct OwnedModule {
+    // borrows from `buf`, so it is declared first to be dropped before it. The 'static
+    // lifetime is never exposed, [OwnedModule::module] shortens it to that of the OwnedModule
+    module: Module<'static>,
+    // never modified or replaced, so the bytes on the heap that `module` borrows don't move
+    buf: Vec<u8>,
+}
+
+impl OwnedModule {
+    // Parse the bytes in `buf` with `parse`, and keep the [Module] with the bytes it borrows
+    fn new<F>(buf: Vec<u8>, parse: F) -> Result<Self>
+        where F: FnOnce(&'static [u8]) -> Result<Module<'static>> {
+        // Safety: the bytes are kept, unchanged and at the same address, for as long as the
+        // module that borrows them
+        let bytes = unsafe { std::slice::from_raw_parts(buf.as_ptr(), buf.len()) };
+        let module = parse(bytes)?;
+        Ok(OwnedModule { module, buf })
+    }
+
+    /// Get the [Module] parsed from the bytes owned by this [OwnedModule], to pass to anything
+    /// that accepts a [Module]
+    pub fn module(&self) -> &Module<'_> {
+        &self.module
+    }
+
+    /// The bytes of the wasm module
+    pub fn bytes(&self) -> &[u8] {
+        &self.buf
+    }
+}
+
+/// A parsed module, that is either a [Module] or an [OwnedModule], so that both can be analyzed
+pub trait AsModule {
+    /// Get the parsed [Module]
+    fn as_module(&self) -> &Module<'_>;
+}
+
+impl AsModule for Module<'_> {
+    fn as_module(&self) -> &Module<'_> {
+        self
+    }
+}
+
+impl AsModule for OwnedModule {
+    fn as_module(&self) -> &Module<'_> {
+        self.module()
     }
 }
 
 impl<'a> fmt::Display for Module<'a> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         writeln!(f, "source: {}", self.source)?;
+        writeln!(f, "kind: {}", self.kind)?;
         writeln!(f, "version: {}", self.version)?;
         writeln!(f, "size: {}", self.file_size)
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+    use std::path::PathBuf;
+    use crate::errors::Error;
+    use super::ModuleKind;
+
+    // The path of the file called `test_file_name` in the test files directory
+    fn test_file(test_file_name: &str) -> PathBuf {
+        PathBuf::from(&format!("{}/tests/test_files/{}", env!("CARGO_MANIFEST_DIR"),
+                               test_file_name))
+    }
+
+    // Compile the WebAssembly text in the test file called `test_file_name` into WASM bytes
+    fn test_wasm(test_file_name: &str) -> Vec<u8> {
+        let text = fs::read_to_string(test_file(test_file_name)).expect("Could not read test file");
+        super::wat_to_wasm(&text).expect("Could not compile test file")
+    }
+
+    #[test]
+    fn test_from_bytes() {
+        let buf = test_wasm("hello_web.wat");
+        let module = super::Module::from_bytes(&buf).expect("Could not parse test wasm");
+        assert_eq!(module.source, "<memory>");
+        assert_eq!(module.kind, ModuleKind::CoreModule);
+        assert_eq!(module.file_size, buf.len() as u64);
+        let analysis = crate::analysis::analyze(&module, true, true, true, true)
+            .expect("Analysis of wasm file failed");
+        assert_eq!(analysis.implemented_function_count, 2);
+    }
+
+    #[test]
+    fn test_parse_validated() {
+        let wasm = test_file("hello_web.wat");
+        let buf = test_wasm("hello_web.wat");
+        super::Module::parse_validated(&wasm, &buf).expect("Could not parse valid wasm");
+
+        // a function section that refers to a type that doesn't exist parses, but is invalid
+        let invalid = b"\0asm\x01\0\0\0\x03\x02\x01\x05";
+        super::Module::from_bytes(invalid).expect("Could not parse invalid wasm");
+        let error = super::Module::parse_validated(&wasm, invalid)
+            .err().expect("Parsed invalid wasm").to_string();
+        assert!(error.contains("at offset"), "{}", error);
+    }
+
+    #[test]
+    fn test_parse_truncated() {
+        let buf = test_wasm("hello_web.wat");
+        let truncated = &buf[..buf.len() - 3];
+        let error = super::Module::from_bytes(truncated)
+            .err().expect("Parsed truncated wasm").to_string();
+        assert!(error.starts_with("parse error at offset 0x"), "{}", error);
+    }
+
+    #[test]
+    fn test_parse_cut_in_half() {
+        let buf = test_wasm("hello_web.wat");
+        let half = &buf[..buf.len() / 2];
+        let error = super::Module::from_bytes(half).err().expect("Parsed half a wasm file");
+        match error {
+            Error::Truncated(consumed) => assert!(consumed <= half.len()),
+            _ => panic!("Unexpected error: {}", error),
+        }
+
+        // a file that is malformed rather than truncated is not reported as truncated
+        let malformed = b"\0wasm\x01\0\0\0";
+        let error = super::Module::from_bytes(malformed).err().expect("Parsed malformed wasm");
+        assert!(!matches!(error, Error::Truncated(_)), "{}", error);
+    }
+
+    #[test]
+    fn test_section_bytes() {
+        // a type section with one function type and a function section with one function
+        let buf = b"\0asm\x01\0\0\0\x01\x04\x01\x60\0\0\x03\x02\x01\0";
+        let module = super::Module::from_bytes(buf).expect("Could not parse test wasm");
+        assert_eq!(module.section_bytes("TypeSection"), Some(&b"\x01\x60\0\0"[..]));
+        assert_eq!(module.section_bytes("FunctionSection"), Some(&b"\x01\0"[..]));
+        assert_eq!(module.section_bytes("CodeSectionStart"), None);
+    }
+
+    #[test]
+    fn test_parse_all_nested_modules() {
+        let buf = test_wasm("nested_modules.wat");
+        let modules = super::Module::parse_all(&buf).expect("Could not parse nested modules");
+        assert_eq!(modules.len(), 2);
+        assert!(modules.iter().all(|module| module.kind == ModuleKind::CoreModule));
+        let function_counts: Vec<u64> = modules.iter()
+            .map(|module| crate::analysis::analyze(module, true, true, false, false)
+                .expect("Analysis of nested module failed").implemented_function_count)
+            .collect();
+        assert_eq!(function_counts, vec!(1, 2));
+
+        // a core module is the only module in itself
+        let buf = b"\0asm\x01\0\0\0";
+        assert_eq!(super::Module::parse_all(buf).expect("Could not parse module").len(), 1);
+    }
+
+    #[test]
+    fn test_to_bytes_round_trip() {
+        let test_files_dir = format!("{}/tests/test_files", env!("CARGO_MANIFEST_DIR"));
+        let mut test_files: Vec<PathBuf> = fs::read_dir(test_files_dir)
+            .expect("Could not read test files")
+            .map(|entry| entry.expect("Could not read test file").path())
+            .collect();
+        test_files.sort();
+        for path in test_files {
+            let file_name = path.file_name().expect("No file name").to_string_lossy();
+            let buf = match path.extension().and_then(|extension| extension.to_str()) {
+                Some("wat") => test_wasm(&file_name),
+                Some("wasm") => fs::read(&path).expect("Could not read wasm file"),
+                _ => continue,
+            };
+            let module = super::Module::from_source(&path, &buf)
+                .expect("Could not parse test wasm");
+            if module.kind == ModuleKind::Component {
+                assert!(module.to_bytes().is_err(), "Encoded component {}", file_name);
+            } else {
+                let bytes = module.to_bytes().expect("Could not encode module");
+                assert_eq!(bytes, buf, "{} changed after encoding", file_name);
+                let round_trip = super::Module::from_bytes(&bytes)
+                    .expect("Could not parse encoded module");
+                assert_eq!(round_trip.sections().expect("No sections"),
+                           module.sections().expect("No sections"));
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_wat() {
+        let source = test_file("hello_web.wat");
+        let owned = super::Module::parse(&source).expect("Could not parse wat file");
+        assert!(owned.bytes().starts_with(b"\0asm"));
+        let analysis = crate::analysis::analyze(&owned, true, true, false, false)
+            .expect("Analysis of wat file failed");
+        assert_eq!(analysis.implemented_function_count, 2);
+
+        match super::wat_to_wasm("(module\n  (func (unknown)))") {
+            Err(Error::Wat { line, column, .. }) =>
+                assert_eq!((line, column), (2, 10)),
+            other => panic!("Expected a Wat error, got: {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn test_from_wat() {
+        let owned = super::Module::from_wat(r#"(module (func (export "f")))"#)
+            .expect("Could not assemble wat");
+        let module = owned.module();
+        assert_eq!(module.source, "<wat>");
+        assert_eq!(module.file_size, owned.bytes().len() as u64);
+        let analysis = crate::analysis::analyze(module, false, true, false, false)
+            .expect("Analysis of wat failed");
+        assert_eq!(analysis.implemented_function_count, 1);
+        assert!(super::Module::from_wat("(module (func (foo)))").is_err());
+    }
+}
@@ -0,0 +1,165 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::errors::*;
+use crate::Module;
+
+// The magic bytes and version that start every core WASM module
+const WASM_HEADER: &[u8; 8] = b"\0asm\x01\0\0\0";
+
+/// Split `module` into one file per section in `directory`, creating it if it doesn't exist,
+/// with the files named and containing the bytes of the sections as in [Module::split]
+/// Return a Result with the paths of the files written
+pub fn split(module: &Module, directory: &Path) -> Result<Vec<PathBuf>> {
+    fs::create_dir_all(directory)
+        .chain_err(|| format!("Could not create directory '{}'", directory.display()))?;
+
+    let mut paths = vec![];
+    for (file_name, bytes) in module.split()? {
+        let path = directory.join(file_name);
+        fs::write(&path, bytes).chain_err(|| format!("Could not write '{}'", path.display()))?;
+        paths.push(path);
+    }
+    Ok(paths)
+}
+
+/// Join the section files in `directory` written by [split] back into a WASM module with
+/// [join_sections], and write it to `destination`
+/// Return a Result with the size of the output file in bytes
+pub fn join(directory: &Path, destination: &Path) -> Result<u64> {
+    let output = join_sections(directory)?;
+    fs::write(destination, &output).chain_err(|| "Could not join")?;
+    Ok(output.len() as u64)
+}
+
+/// Read the section files in `directory` written by [split], in the order of the number
+/// they start with, and concatenate them after the WASM header. Other files are ignored.
+/// Return a Result with the bytes of the joined module, which has been validated with the
+/// proposals in [crate::parse::features]
+pub fn join_sections(directory: &Path) -> Result<Vec<u8>> {
+    let entries = fs::read_dir(directory)
+        .chain_err(|| format!("Could not read directory '{}'", directory.display()))?;
+    let mut section_files = BTreeMap::new();
+    for entry in entries {
+        let path = entry?.path();
+        let file_name = path.file_name().map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let number = match file_name.split_once('_') {
+            Some((number, _)) if file_name.ends_with(".bin") => number.parse::<usize>().ok(),
+            _ => None,
+        };
+        if let Some(number) = number {
+            if let Some(previous) = section_files.insert(number, path.clone()) {
+                return Err(Error::DuplicateSectionFile { number, first: previous, second: path });
+            }
+        }
+    }
+    if section_files.is_empty() {
+        return Err(Error::NoSectionFiles(directory.to_path_buf()));
+    }
+
+    let mut output = WASM_HEADER.to_vec();
+    for (expected, (number, path)) in section_files.into_iter().enumerate() {
+        if number != expected {
+            return Err(Error::MissingSectionFile { number: expected,
+                                                   directory: directory.to_path_buf() });
+        }
+        output.extend(fs::read(&path)?);
+    }
+    crate::parse::validate(&output)
+        .chain_err(|| format!("The sections in '{}' do not join into a valid WASM module",
+                              directory.display()))?;
+
+    Ok(output)
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+    use std::path::PathBuf;
+    use wax::Glob;
+    use crate::Module;
+    use crate::errors::Error;
+    use crate::parse::ModuleKind;
+    use super::{join_sections, split};
+
+    #[test]
+    fn test_split() {
+        let source = PathBuf::from(&format!("{}/tests/test_files/compare_switch_stripped.wasm",
+                                            env!("CARGO_MANIFEST_DIR")));
+        let mut buf = fs::read(source).expect("Could not read test file");
+        buf.push(0);
+        wasm_encoder::Encode::encode(&wasm_encoder::CustomSection {
+            name: "my/section".into(),
+            data: vec!(1, 2, 3).into(),
+        }, &mut buf);
+        let module = Module::from_bytes(&buf).expect("Could not parse test file");
+
+        let directory = std::env::temp_dir().join(format!("split_{}", std::process::id()));
+        let paths = split(&module, &directory).expect("Could not split module");
+        let file_names: Vec<String> = paths.iter()
+            .map(|path| path.file_name().expect("No file name").to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(file_names.first().map(String::as_str), Some("00_TypeSection.bin"));
+        let last = format!("{:02}_CustomSection_my_section.bin", paths.len() - 1);
+        assert_eq!(file_names.last(), Some(&last));
+
+        // the sections follow the header with nothing between them
+        let mut contents = buf[..8].to_vec();
+        for path in &paths {
+            contents.extend(fs::read(path).expect("Could not read section file"));
+        }
+        assert_eq!(contents, buf);
+
+        let _ = fs::remove_dir_all(&directory);
+    }
+
+    #[test]
+    fn test_split_join_round_trip() {
+        let test_files_dir = PathBuf::from(&format!("{}/tests/test_files",
+                                                    env!("CARGO_MANIFEST_DIR")));
+        let glob = Glob::new("*.{wasm,wat}").expect("Globbing error");
+        for entry in glob.walk(test_files_dir) {
+            let entry = entry.expect("Could not read test file");
+            let file_name = entry.path().file_name().expect("No file name").to_string_lossy();
+            let buf = match entry.path().extension() {
+                Some(extension) if extension == "wat" => {
+                    let text = fs::read_to_string(entry.path()).expect("Could not read test file");
+                    crate::parse::wat_to_wasm(&text).expect("Could not compile test file")
+                }
+                _ => fs::read(entry.path()).expect("Could not read test file"),
+            };
+            let module = Module::from_bytes(&buf).expect("Could not parse test file");
+            // components can't be split into sections
+            if module.kind == ModuleKind::Component {
+                assert!(module.split().is_err(), "Split component {}", file_name);
+                continue;
+            }
+
+            let directory = std::env::temp_dir()
+                .join(format!("join_{}_{}", file_name, std::process::id()));
+            let paths = split(&module, &directory).expect("Could not split module");
+            assert_eq!(join_sections(&directory).expect("Could not join sections"), buf);
+
+            // a section cut short is not valid
+            let code_section = paths.iter()
+                .find(|path| path.to_string_lossy().ends_with("_CodeSectionStart.bin"));
+            if let Some(code_section) = code_section {
+                let code = fs::read(code_section).expect("Could not read section file");
+                fs::write(code_section, &code[..code.len() / 2])
+                    .expect("Could not write section file");
+                assert!(join_sections(&directory).is_err(), "Joined truncated {}", file_name);
+            }
+
+            // nor is a module with a section missing
+            if paths.len() > 1 {
+                fs::remove_file(&paths[0]).expect("Could not remove section file");
+                let error = join_sections(&directory).expect_err("Joined with a missing section");
+                assert!(matches!(error, Error::MissingSectionFile { number: 0, .. }),
+                        "Unexpected error: {error}");
+            }
+
+            let _ = fs::remove_dir_all(&directory);
+        }
+    }
+}
@@ -0,0 +1,79 @@
+use std::process::Command;
+
+#[test]
+fn missing_file_argument() {
+    let output = Command::new(env!("CARGO_BIN_EXE_wazm"))
+        .output().expect("Could not run wazm");
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Usage:"), "No usage message in: {stderr}");
+    assert!(!stderr.contains("panicked"), "wazm panicked: {stderr}");
+}
+
+#[test]
+fn output_directory_missing() {
+    let output = Command::new(env!("CARGO_BIN_EXE_wazm"))
+        .arg("--keep")
+        .arg("--output").arg("tests/no_such_directory/out.wz")
+        .arg("tests/test_files/compare_switch_stripped.wasm")
+        .output().expect("Could not run wazm");
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("'tests/no_such_directory' of the output file does not exist"),
+            "Unexpected error: {stderr}");
+}
+
+#[test]
+fn split_and_join() {
+    let source = "tests/test_files/compare_switch_stripped.wasm";
+    let directory = std::env::temp_dir().join(format!("cli_split_{}", std::process::id()));
+    let joined = directory.with_extension("wasm");
+    let output = Command::new(env!("CARGO_BIN_EXE_wazm"))
+        .arg("--split").arg(&directory)
+        .arg(source)
+        .output().expect("Could not run wazm");
+    assert!(output.status.success(), "Split failed: {}", String::from_utf8_lossy(&output.stderr));
+
+    let output = Command::new(env!("CARGO_BIN_EXE_wazm"))
+        .arg("--join").arg(&directory).arg(&joined)
+        .output().expect("Could not run wazm");
+    assert!(output.status.success(), "Join failed: {}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(std::fs::read(&joined).expect("Could not read joined file"),
+               std::fs::read(source).expect("Could not read source file"));
+
+    let _ = std::fs::remove_dir_all(&directory);
+    let _ = std::fs::remove_file(&joined);
+}
+
+#[test]
+fn analyze_mmap() {
+    let analyze = |mmap: bool| {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_wazm"));
+        command.arg("--analyze");
+        if mmap {
+            command.arg("--mmap");
+        }
+        let output = command.arg("tests/test_files/compare_switch_stripped.wasm")
+            .output().expect("Could not run wazm");
+        assert!(output.status.success(), "Analysis failed: {}",
+                String::from_utf8_lossy(&output.stderr));
+        output.stdout
+    };
+    let read = analyze(false);
+    assert!(!read.is_empty());
+    assert_eq!(String::from_utf8_lossy(&analyze(true)), String::from_utf8_lossy(&read));
+}
+
+#[test]
+fn markdown_conflicts() {
+    for arg in [vec!["--summary"], vec!["--disassemble", "0"], vec!["--max-function-size", "10"]] {
+        let output = Command::new(env!("CARGO_BIN_EXE_wazm"))
+            .arg("--analyze").arg("--analyze-functions").arg("--markdown")
+            .args(&arg)
+            .arg("tests/test_files/compare_switch_stripped.wasm")
+            .output().expect("Could not run wazm");
+        assert!(!output.status.success(), "{:?} did not conflict with --markdown", arg);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(stderr.contains("cannot be used with"), "Unexpected error: {stderr}");
+    }
+}
@@ -0,0 +1,9 @@
+(module
+    ;; Atomic operators on memory that is not shared
+    (memory 1)
+
+    (func (export "load") (param $address i32) (result i32)
+        local.get $address
+        i32.atomic.load
+        )
+)
@@ -0,0 +1,23 @@
+(module
+    (memory  1)
+
+    ;; Copy a block of memory and then clear the source
+    (func (export  "move") (param  $dest i32) (param  $src i32) (param  $len i32)
+        local.get $dest
+        local.get $src
+        local.get $len
+        memory.copy
+        local.get $src
+        i32.const 0
+        local.get $len
+        memory.fill
+        )
+
+    ;; Clear a block of memory
+    (func (export  "clear") (param  $dest i32) (param  $len i32)
+        local.get $dest
+        i32.const 0
+        local.get $len
+        memory.fill
+        )
+)
@@ -0,0 +1,16 @@
+(module
+    ;; Uses one or two of each control flow operator, except call_indirect
+    (func $check (param $n i32) (result i32)
+        (if (result i32) (local.get $n)
+            (then (i32.const 1))
+            (else (unreachable))))
+    (func (export "count") (param $n i32) (result i32)
+        (block $done
+            (loop $again
+                (br_if $done (i32.eqz (local.get $n)))
+                (local.set $n (i32.sub (local.get $n) (i32.const 1)))
+                (br $again)))
+        (block $default
+            (br_table $default (local.get $n)))
+        (return (call $check (local.get $n))))
+)
@@ -0,0 +1,17 @@
+(module
+    (memory 1)
+
+    ;; One active segment, and two passive segments of which only the first is initialized
+    (data (i32.const 0) "active")
+    (data $greeting "hello")
+    (data $unused "never copied")
+
+    (func (export "init") (param $dest i32)
+        local.get $dest
+        i32.const 0
+        i32.const 5
+        memory.init $greeting
+        data.drop $greeting
+        data.drop $unused
+        )
+)
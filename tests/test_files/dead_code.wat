@@ -0,0 +1,15 @@
+(module
+    ;; Has instructions that can never be reached after unconditional branches
+    (func (export "dead") (param $n i32) (result i32)
+        (block $exit
+            (br $exit)
+            (nop)
+            (drop (i32.const 1)))
+        (if (local.get $n)
+            (then
+                (unreachable)
+                (block (nop)))
+            (else (nop)))
+        (return (i32.const 2))
+        (i32.const 3))
+)
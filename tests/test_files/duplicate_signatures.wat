@@ -0,0 +1,20 @@
+(module
+    ;; Types 0 and 2 are identical, as are types 1 and 3, and all of them are referenced
+    (type $binary (func (param i32 i32) (result i32)))
+    (type $unary (func (param i32) (result i32)))
+    (type $binary_again (func (param i32 i32) (result i32)))
+    (type $unary_again (func (param i32) (result i32)))
+    (import "env" "add" (func $add (type $binary_again)))
+    (table 2 funcref)
+    (elem (i32.const 0) func $double $add)
+    (func $double (type $unary_again) (param i32) (result i32)
+        (i32.mul (local.get 0) (i32.const 2)))
+    (func (export "apply") (type $binary) (param i32 i32) (result i32)
+        (local.get 0)
+        (block (type $unary_again) (param i32) (result i32)
+            (call_indirect (type $unary_again) (i32.const 0)))
+        (local.get 1)
+        (call_indirect (type $binary_again) (i32.const 1)))
+    (func (export "square") (type $unary) (param i32) (result i32)
+        (i32.mul (local.get 0) (local.get 0)))
+)
@@ -0,0 +1,7 @@
+(module
+    ;; Types 0 and 2 are identical, types 1 and 3 have the same arity but different value types
+    (type  (func (param  i32  i32) (result i32)))
+    (type  (func (param  i64  i64) (result i64)))
+    (type  (func (param  i32  i32) (result i32)))
+    (type  (func (param  i64  i64) (result i32)))
+)
@@ -0,0 +1,12 @@
+(module
+    (func $helper)
+    (func $main
+        call $helper
+    )
+    (func $start
+        call $main
+    )
+    (func $unused)
+    (export "main" (func $main))
+    (export "_start" (func $start))
+)
@@ -0,0 +1,12 @@
+(module
+    ;; Throw an exception from one function and catch it in another
+    (tag $error (param i32))
+    (func $fail (param $code i32)
+        (throw $error (local.get $code)))
+    (func (export "safe_fail") (param $code i32) (result i32)
+        try (result i32)
+            (call $fail (local.get $code))
+            (i32.const 0)
+        catch $error
+        end)
+)
@@ -0,0 +1,13 @@
+(module
+    ;; Imports and exports of memories, globals and tables as well as functions
+    (import "env" "memory" (memory 1))
+    (import "env" "stack_pointer" (global $sp (mut i32)))
+    (import "env" "table" (table 1 funcref))
+    (import "env" "abort" (func $abort))
+    (global $counter (export "counter") (mut i32) (i32.const 0))
+    (table $own (export "own_table") 2 funcref)
+    (export "memory" (memory 0))
+    (export "stack_pointer" (global $sp))
+    (func (export "run")
+        (call $abort))
+)
@@ -0,0 +1,13 @@
+(module
+    ;; Convert between integers and floats, and do some floating point arithmetic
+    (func (export  "average") (param  $a i32) (param  $b i32) (result i32)
+        local.get $a
+        f64.convert_i32_s
+        local.get $b
+        f64.convert_i32_s
+        f64.add
+        f64.const 2
+        f64.div
+        i32.trunc_f64_s
+        )
+)
@@ -0,0 +1,7 @@
+(module
+    ;; Globals initialized by different kinds of constant expression
+    (import "env" "heap_base" (global $heap_base i32))
+    (global $stack_pointer (mut i32) (i32.const 1048576))
+    (global $heap (export "heap") i32 (global.get $heap_base))
+    (global $scale f64 (f64.const 0.5))
+)
@@ -0,0 +1,12 @@
+(module
+    ;; Implemented functions are indexed after the two imported functions
+    (import "env" "log" (func $log (param i32)))
+    (import "env" "now" (func $now (result i32)))
+    (func $main (export "main") (local i32)
+        (local.set 0 (call $elapsed))
+        (call $log (local.get 0)))
+    (func $elapsed (result i32)
+        (call $now))
+    (func $unused (result i32) (local i32 i64)
+        (local.get 0))
+)
@@ -0,0 +1,4 @@
+(module
+    ;; A 64-bit linear memory, exported so it is not optimized away
+    (memory (export "memory") i64 1)
+)
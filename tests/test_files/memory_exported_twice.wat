@@ -0,0 +1,9 @@
+(module
+    ;; Exports the same memory and global under two names each
+    (memory 1)
+    (global $flag i32 (i32.const 1))
+    (export "memory" (memory 0))
+    (export "heap" (memory 0))
+    (export "flag" (global $flag))
+    (export "enabled" (global $flag))
+)
@@ -0,0 +1,15 @@
+(module
+    (memory 1)
+    (func $grow (param i32) (result i32)
+        local.get 0
+        memory.grow
+        drop
+        i32.const 1
+        memory.grow
+    )
+    (func $size (result i32)
+        memory.size
+    )
+    (export "grow" (func $grow))
+    (export "size" (func $size))
+)
@@ -0,0 +1,11 @@
+(module
+    ;; Return both the quotient and the remainder of a division
+    (func (export  "divmod") (param  $a i32) (param  $b i32) (result i32 i32)
+        local.get $a
+        local.get $b
+        i32.div_u
+        local.get $a
+        local.get $b
+        i32.rem_u
+        )
+)
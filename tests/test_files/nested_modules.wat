@@ -0,0 +1,11 @@
+(component
+    (core module
+        (func (export "first"))
+    )
+    (component
+        (core module
+            (func (export "second"))
+            (func (export "third"))
+        )
+    )
+)
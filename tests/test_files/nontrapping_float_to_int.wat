@@ -0,0 +1,11 @@
+(module
+    ;; Convert floats to integers, saturating rather than trapping when out of range
+    (func (export "to_int") (param $value f64) (result i32)
+        local.get $value
+        i32.trunc_sat_f64_s
+        )
+    (func (export "to_long") (param $value f32) (result i64)
+        local.get $value
+        i64.trunc_sat_f32_u
+        )
+)
@@ -0,0 +1,13 @@
+(module
+    ;; Table of host references
+    (table  $objects 4 externref)
+
+    ;; Store a host reference in the table, returning if it was null
+    (func (export  "store") (param  $slot i32) (param  $object externref) (result i32)
+        local.get $slot
+        local.get $object
+        table.set $objects
+        local.get $object
+        ref.is_null
+        )
+)
@@ -0,0 +1,9 @@
+(module
+    ;; Fused multiply-add of vectors, which may or may not round the intermediate result
+    (func (export "madd") (param $a v128) (param $b v128) (param $c v128) (result v128)
+        local.get $a
+        local.get $b
+        local.get $c
+        f32x4.relaxed_madd
+        )
+)
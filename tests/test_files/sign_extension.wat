@@ -0,0 +1,9 @@
+(module
+    ;; Sign extend the low 8 bits of a value to 32 and then to 64 bits
+    (func (export "extend") (param $value i32) (result i64)
+        local.get $value
+        i32.extend8_s
+        i64.extend_i32_s
+        i64.extend32_s
+        )
+)
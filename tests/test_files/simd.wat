@@ -0,0 +1,11 @@
+(module
+    ;; Add two vectors of four 32-bit integers
+    (memory 1)
+    (func (export  "add") (param  i32  i32) (result v128)
+        local.get 0
+        v128.load
+        local.get 1
+        v128.load
+        i32x4.add
+        )
+)
@@ -0,0 +1,11 @@
+(module
+    (global  $initialized (mut i32) (i32.const 0))
+
+    ;; Run when the module is instantiated
+    (func  $init
+        i32.const 1
+        global.set $initialized
+        )
+
+    (start  $init)
+)
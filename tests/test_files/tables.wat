@@ -0,0 +1,24 @@
+(module
+    ;; Imported table that the host may also write to
+    (import  "js"  "table" (table  1  funcref))
+
+    ;; Table used for dynamic dispatch of local functions
+    (table  2  10  funcref)
+    (elem  (table 1) (i32.const 0) func $first $second)
+
+    (type  $return_i32 (func (result i32)))
+
+    (func  $first (result i32)
+        i32.const 1
+        )
+
+    (func  $second (result i32)
+        i32.const 2
+        )
+
+    ;; Dispatch to one of the functions in the table
+    (func (export  "dispatch") (param  i32) (result i32)
+        local.get 0
+        call_indirect 1 (type $return_i32)
+        )
+)
@@ -0,0 +1,14 @@
+(module
+    ;; Count down using a direct tail call into a helper, that tail calls through a table
+    (type $countdown (func (param i32) (result i32)))
+    (table 1 funcref)
+    (elem (i32.const 0) func $step)
+    (func $countdown (export "countdown") (type $countdown) (param $n i32) (result i32)
+        (if (result i32) (i32.eqz (local.get $n))
+            (then (i32.const 0))
+            (else (return_call $step (local.get $n)))))
+    (func $step (type $countdown) (param $n i32) (result i32)
+        (return_call_indirect (type $countdown)
+            (i32.sub (local.get $n) (i32.const 1))
+            (i32.const 0)))
+)
@@ -0,0 +1,11 @@
+(module
+    ;; Memory shared between threads
+    (import  "js"  "mem" (memory  1  1  shared))
+
+    ;; Atomically increment the counter at the given address, returning the old value
+    (func (export  "increment") (param  $address i32) (result i32)
+        local.get $address
+        i32.const 1
+        i32.atomic.rmw.add
+        )
+)
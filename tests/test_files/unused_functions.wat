@@ -0,0 +1,37 @@
+(module
+    ;; Has functions that can't be reached from the exports, start function or table
+    (import "env" "log" (func $log (param i32)))
+
+    (table 1 funcref)
+    (elem (i32.const 0) func $in_table)
+
+    ;; Only referred to from a global, so it can still be called through the reference
+    (global $callback funcref (ref.func $referenced))
+
+    ;; Never called, and the only user of its type
+    (func $unused (param f64) (result f64)
+        local.get 0
+        call $also_unused)
+
+    ;; Only called from an unused function
+    (func $also_unused (param f64) (result f64)
+        local.get 0)
+
+    (func $helper (param i32) (result i32)
+        local.get 0
+        call $log
+        local.get 0)
+
+    (func $in_table (result i32)
+        i32.const 1
+        call $helper)
+
+    (func $referenced)
+
+    (func (export "run") (param i32) (result i32)
+        local.get 0
+        call $helper
+        i32.const 0
+        call_indirect (result i32)
+        i32.add)
+)
@@ -23,6 +23,7 @@ fn compression() {
         let original_size = path.metadata().unwrap().len();
         println!("{} {}", path.file_name().unwrap().to_string_lossy(), original_size);
 
+        let mut sizes = vec!();
         for (tool, extension) in TOOL_LIST {
             let mut test_input_file = test_output_dir.clone();
             test_input_file.push(path.file_name().unwrap());
@@ -37,6 +38,8 @@ fn compression() {
             let new_size = output_path.metadata()
                 .expect("Could not get file metadata").len();
             println!("{tool} {new_size} {}%", (new_size * 100) / original_size);
+            sizes.push(new_size);
         }
+        assert!(sizes[0] < sizes[1], "wazm did not compress better than gzip");
     }
 }
\ No newline at end of file